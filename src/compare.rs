@@ -0,0 +1,39 @@
+// The MIT License (MIT)
+
+// Copyright (c) 2021 AnonymousDapper
+
+// Byte and register checks, used by script conditions and environment done terms
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Compare {
+    Eq,
+    Ne,
+    Lt,
+    Le,
+    Gt,
+    Ge,
+}
+
+impl Compare {
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "==" => Some(Self::Eq),
+            "!=" => Some(Self::Ne),
+            "<" => Some(Self::Lt),
+            "<=" => Some(Self::Le),
+            ">" => Some(Self::Gt),
+            ">=" => Some(Self::Ge),
+            _ => None,
+        }
+    }
+
+    pub fn test(self, a: u32, b: u32) -> bool {
+        match self {
+            Self::Eq => a == b,
+            Self::Ne => a != b,
+            Self::Lt => a < b,
+            Self::Le => a <= b,
+            Self::Gt => a > b,
+            Self::Ge => a >= b,
+        }
+    }
+}
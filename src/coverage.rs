@@ -0,0 +1,223 @@
+// The MIT License (MIT)
+
+// Copyright (c) 2021 AnonymousDapper
+
+use std::fmt::Write;
+use std::ops::Range;
+
+use super::flow::FlowGraph;
+use super::System;
+
+const EXECUTED: u8 = 1;
+const READ: u8 = 2;
+const WRITTEN: u8 = 4;
+
+// Bytes per line of the coverage map
+const MAP_WIDTH: usize = 32;
+
+// Which addresses were executed, read as data or written
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Coverage {
+    flags: Vec<u8>,
+}
+
+impl Coverage {
+    pub fn new(size: usize) -> Self {
+        Self {
+            flags: vec![0; size],
+        }
+    }
+
+    fn mark(&mut self, addr: u16, flag: u8) {
+        let len = self.flags.len();
+        self.flags[addr as usize % len] |= flag;
+    }
+
+    // Both bytes of the instruction
+    pub fn mark_executed(&mut self, addr: u16) {
+        self.mark(addr, EXECUTED);
+        self.mark(addr.wrapping_add(1), EXECUTED);
+    }
+
+    pub fn mark_read(&mut self, addr: u16) {
+        self.mark(addr, READ);
+    }
+
+    pub fn mark_written(&mut self, addr: u16) {
+        self.mark(addr, WRITTEN);
+    }
+
+    fn has(&self, addr: usize, flag: u8) -> bool {
+        self.flags.get(addr).is_some_and(|flags| flags & flag != 0)
+    }
+
+    pub fn executed(&self, addr: u16) -> bool {
+        self.has(addr as usize, EXECUTED)
+    }
+
+    pub fn read(&self, addr: u16) -> bool {
+        self.has(addr as usize, READ)
+    }
+
+    pub fn written(&self, addr: u16) -> bool {
+        self.has(addr as usize, WRITTEN)
+    }
+
+    // One character per byte, executed code shown over writes over reads
+    fn symbol(&self, addr: usize) -> char {
+        if self.has(addr, EXECUTED) {
+            'x'
+        } else if self.has(addr, WRITTEN) {
+            'w'
+        } else if self.has(addr, READ) {
+            'r'
+        } else {
+            '.'
+        }
+    }
+
+    fn rom(system: &System) -> Range<usize> {
+        let start = system.load_address as usize;
+        start..start + system.rom.len()
+    }
+
+    pub fn summary(&self, system: &System) -> String {
+        let rom = Self::rom(system);
+        let size = rom.len().max(1) as f64;
+        let count = |flag| rom.clone().filter(|&addr| self.has(addr, flag)).count();
+        let untouched = rom
+            .clone()
+            .filter(|&addr| self.flags.get(addr).is_some_and(|&flags| flags == 0))
+            .count();
+
+        let mut summary = format!(
+            "ROM {:#06x}-{:#06x}, {} bytes\n",
+            rom.start,
+            rom.end,
+            rom.len()
+        );
+
+        for (name, bytes) in [
+            ("executed", count(EXECUTED)),
+            ("read", count(READ)),
+            ("written", count(WRITTEN)),
+            ("untouched", untouched),
+        ] {
+            writeln!(
+                summary,
+                "{:<12}{:>6} bytes {:>6.1}%",
+                name,
+                bytes,
+                bytes as f64 * 100.0 / size
+            )
+            .ok();
+        }
+
+        // Against the code that can be found by following the program from its entry point
+        let graph = FlowGraph::build(system);
+        let instructions: Vec<u16> = graph
+            .blocks()
+            .flat_map(|block| block.instructions())
+            .collect();
+        let reached = instructions
+            .iter()
+            .filter(|&&addr| self.executed(addr))
+            .count();
+
+        writeln!(
+            summary,
+            "{:<12}{:>6} / {} instructions {:.1}%",
+            "code",
+            reached,
+            instructions.len(),
+            reached as f64 * 100.0 / instructions.len().max(1) as f64
+        )
+        .ok();
+
+        let subroutines = graph.subroutines();
+        let missed: Vec<String> = subroutines
+            .iter()
+            .filter(|sub| !self.executed(sub.entry))
+            .map(|sub| match system.symbols().name(sub.entry) {
+                Some(name) => name.to_string(),
+                None => format!("{:#06x}", sub.entry),
+            })
+            .collect();
+
+        write!(
+            summary,
+            "{:<12}{:>6} / {} reached",
+            "subroutines",
+            subroutines.len() - missed.len(),
+            subroutines.len()
+        )
+        .ok();
+
+        if !missed.is_empty() {
+            write!(summary, ", missed {}", missed.join(" ")).ok();
+        }
+        summary.push('\n');
+
+        summary
+    }
+
+    // The ROM and anything after it that was touched, a line of characters per 32 bytes
+    pub fn map(&self, system: &System) -> String {
+        let rom = Self::rom(system);
+        let end = (rom.end..self.flags.len())
+            .rev()
+            .find(|&addr| self.flags[addr] != 0)
+            .map_or(rom.end, |addr| addr + 1);
+
+        let mut map = String::from("; x executed, w written, r read, . untouched\n");
+        let first = rom.start - rom.start % MAP_WIDTH;
+
+        for line in (first..end).step_by(MAP_WIDTH) {
+            let row: String = (line..line + MAP_WIDTH)
+                .map(|addr| {
+                    if (rom.start..end).contains(&addr) {
+                        self.symbol(addr)
+                    } else {
+                        ' '
+                    }
+                })
+                .collect();
+
+            writeln!(map, "{:#06x} {}", line, row.trim_end()).ok();
+        }
+
+        map
+    }
+
+    // Every instruction slot of the ROM with `text` for its disassembly, and how it was used
+    pub fn listing<F: Fn(u16) -> String>(&self, system: &System, text: F) -> String {
+        let mut listing = String::new();
+
+        for addr in Self::rom(system).step_by(2) {
+            let addr = addr as u16;
+            let flags: String = [
+                (self.executed(addr), 'x'),
+                (self.read(addr) || self.read(addr.wrapping_add(1)), 'r'),
+                (
+                    self.written(addr) || self.written(addr.wrapping_add(1)),
+                    'w',
+                ),
+            ]
+            .iter()
+            .map(|&(set, c)| if set { c } else { '-' })
+            .collect();
+
+            writeln!(
+                listing,
+                "{} {:#06x} | ({:#06x}) {}",
+                flags,
+                addr,
+                system.mem.read_u16(addr),
+                text(addr)
+            )
+            .ok();
+        }
+
+        listing
+    }
+}
@@ -2,87 +2,328 @@
 
 // Copyright (c) 2021 AnonymousDapper
 
+use super::layout::{Layout, REGISTERS_HEIGHT};
+use super::memview::MemoryView;
+use super::platform::Platform;
+use super::spriteview::SpriteView;
 use super::System;
 
-use std::collections::VecDeque;
+use std::collections::BTreeMap;
+use std::io::Write;
+
+// Drops the colours from a line of disassembly, for output that isn't going to a terminal
+pub fn strip_escapes(s: &str) -> String {
+    let mut plain = String::with_capacity(s.len());
+    let mut chars = s.chars();
+
+    while let Some(c) = chars.next() {
+        if c == '\x1b' {
+            // Everything up to and including the final letter of the sequence
+            chars.by_ref().find(|c| c.is_ascii_alphabetic());
+        } else {
+            plain.push(c);
+        }
+    }
+
+    plain
+}
+
+// Cuts a coloured line down to `width` visible characters, keeping its escapes
+fn clip_escaped(s: &str, width: usize) -> String {
+    let mut clipped = String::with_capacity(s.len());
+    let mut chars = s.chars();
+    let mut shown = 0;
+
+    while let Some(c) = chars.next() {
+        if c == '\x1b' {
+            clipped.push(c);
+
+            for c in chars.by_ref() {
+                clipped.push(c);
+
+                if c.is_ascii_alphabetic() {
+                    break;
+                }
+            }
+        } else if shown < width {
+            clipped.push(c);
+            shown += 1;
+        }
+    }
+
+    clipped
+}
 
 #[derive(Debug)]
 pub struct Disassembler {
-    dis_buffer: VecDeque<String>,
+    labels: BTreeMap<u16, String>, // Jump and call targets
+    selected: Option<u16>,         // Scrolled away from PC to this line
+    memory: MemoryView,
+    sprite: SpriteView,
+    patches: usize, // Self-modifying code seen at the last label scan
 }
 
 impl Disassembler {
     #[allow(clippy::new_without_default)]
     pub fn new() -> Self {
         Self {
-            dis_buffer: VecDeque::new(),
+            labels: BTreeMap::new(),
+            selected: None,
+            memory: MemoryView::new(),
+            sprite: SpriteView::new(),
+            patches: 0,
         }
     }
 
-    pub fn print_state(&self, system: &System) {
-        print!("\x1b[4;145H");
-        let mut buffer = String::from("\x1b[32;40;4;1m< Registers >\x1b[0m\x1b[6;145H");
+    // Names every JP and CALL target in the ROM, the ROM is assumed to be code from the start.
+    // Memory is read as it is now, so patched code is seen as patched
+    pub fn scan_labels(&mut self, system: &System) {
+        let start = system.load_address;
+        let end = start as usize + system.rom.len();
+
+        self.labels.clear();
+        self.patches = system.smc().map_or(0, |smc| smc.len());
+
+        for addr in (start as usize..end.saturating_sub(1)).step_by(2) {
+            let op = system.mem.read_u16(addr as u16).to_be_bytes();
+            let target = u16::from_be_bytes(op) & 0x0fff;
+
+            // Two bytes of data can look like anything, so only targets inside the ROM count
+            if target < start || target as usize >= end {
+                continue;
+            }
+
+            match op[0] >> 4 {
+                0x1 => {
+                    self.labels
+                        .entry(target)
+                        .or_insert_with(|| format!("loc_{:03x}", target));
+                }
+                0x2 => {
+                    self.labels.insert(target, format!("sub_{:03x}", target));
+                }
+                _ => {}
+            }
+        }
+    }
+
+    // Loaded symbols come first, then the names made up by `scan_labels`
+    pub fn label<'a>(&'a self, system: &'a System, addr: u16) -> Option<&'a str> {
+        system
+            .symbols()
+            .name(addr)
+            .or_else(|| self.labels.get(&addr).map(String::as_str))
+    }
+
+    // The line the pane is centred on, PC unless it has been scrolled
+    pub fn selected(&self, system: &System) -> u16 {
+        self.selected.unwrap_or(system.pc)
+    }
+
+    pub fn scroll(&mut self, system: &System, lines: i32) {
+        let size = system.mem.ram.len() as i32;
+        let addr = self.selected(system) as i32 + lines * 2;
+
+        self.selected = Some(addr.rem_euclid(size) as u16);
+    }
+
+    pub fn follow_pc(&mut self) {
+        self.selected = None;
+    }
+
+    pub fn toggle_breakpoint(&self, system: &mut System) {
+        system.toggle_breakpoint(self.selected(system));
+    }
+
+    pub fn memory_mut(&mut self) -> &mut MemoryView {
+        &mut self.memory
+    }
+
+    pub fn sprite_mut(&mut self) -> &mut SpriteView {
+        &mut self.sprite
+    }
+
+    pub fn print_state(&self, system: &System, layout: &Layout, out: &mut dyn Write) {
+        let mut buffer = String::new();
+
+        if let Some(pane) = layout.registers {
+            let col = pane.col;
+
+            // Collapsed pane puts DT/ST beside PC/IR and splits V0-VF into two columns
+            let compact = pane.height < REGISTERS_HEIGHT;
+            let (timer_row, timer_col, reg_row) = if compact {
+                (pane.row + 2, col + 13, pane.row + 5)
+            } else {
+                (pane.row + 5, col, pane.row + 8)
+            };
 
-        buffer.push_str(&format!(
-            "PC : \x1b[93;40m{:#06x}\x1b[0m\x1b[7;145H",
-            system.pc
-        ));
+            buffer.push_str(&format!(
+                "\x1b[{};{}H\x1b[32;40;4;1m< Registers >\x1b[0m",
+                pane.row, col
+            ));
 
-        buffer.push_str(&format!(
-            "IR : \x1b[93;40m{:#06x}\x1b[0m\x1b[9;145H",
-            system.ir
-        ));
+            buffer.push_str(&format!(
+                "\x1b[{};{}HPC : \x1b[93;40m{:#06x}\x1b[0m",
+                pane.row + 2,
+                col,
+                system.pc
+            ));
 
-        buffer.push_str(&format!(
-            "DT : \x1b[94;40m{:#04x}\x1b[0m\x1b[10;145H",
-            system.dt.get_no_mod()
-        ));
+            buffer.push_str(&format!(
+                "\x1b[{};{}HIR : \x1b[93;40m{:#06x}\x1b[0m",
+                pane.row + 3,
+                col,
+                system.ir
+            ));
 
-        buffer.push_str(&format!(
-            "ST : \x1b[94;40m{:#04x}\x1b[0m\x1b[12;145H",
-            system.st
-        ));
+            buffer.push_str(&format!(
+                "\x1b[{};{}HDT : \x1b[94;40m{:#04x}\x1b[0m",
+                timer_row,
+                timer_col,
+                system.dt.get()
+            ));
 
-        for (i, v) in system.registers.iter().enumerate() {
             buffer.push_str(&format!(
-                "\x1b[95mV{:x}\x1b[30m : {:#04x}\x1b[{};145H",
-                i,
-                v,
-                i + 13
+                "\x1b[{};{}HST : \x1b[94;40m{:#04x}\x1b[0m",
+                timer_row + 1,
+                timer_col,
+                system.st.get()
             ));
+
+            for (i, v) in system.registers.iter().enumerate() {
+                let (row, col) = if compact {
+                    (reg_row + i % 8, col + (i / 8) * 13)
+                } else {
+                    (reg_row + i, col)
+                };
+
+                buffer.push_str(&format!(
+                    "\x1b[{};{}H\x1b[95mV{:x}\x1b[30m : {:#04x}",
+                    row, col, i, v
+                ));
+            }
         }
 
-        buffer.push_str("\x1b[4;170H\x1b[32;40;4;1m< Stack >\x1b[0m\x1b[6;170H");
+        if let Some(pane) = layout.stack {
+            buffer.push_str(&format!(
+                "\x1b[{};{}H\x1b[32;40;4;1m< Stack >\x1b[0m",
+                pane.row, pane.col
+            ));
+
+            let stack = system.mem.stack();
+            let depth = system.mem.stack_depth();
+            let colour = if stack.len() >= depth { 91 } else { 37 };
 
-        for entry in system.mem.stack.iter().rev() {
-            buffer.push_str(&format!("[ {:#04x} ]\x1b[1B\x1b[9D", entry));
+            buffer.push_str(&format!(
+                "\x1b[{};{}H\x1b[{}m{:>2} / {:<2}\x1b[0m{:width$}",
+                pane.row + 1,
+                pane.col,
+                colour,
+                stack.len(),
+                depth,
+                "",
+                width = pane.width.saturating_sub(7)
+            ));
+
+            // Deepest entries are dropped first when the pane is too short
+            let mut entries = stack.iter().rev();
+
+            for row in pane.row + 2..pane.bottom() {
+                let entry = match entries.next() {
+                    Some(&entry) => match system.symbols().describe(entry) {
+                        Some(name) => format!("{:.width$}", name, width = pane.width),
+                        None => format!("[ {:#04x} ]", entry),
+                    },
+                    None => String::new(),
+                };
+
+                buffer.push_str(&format!(
+                    "\x1b[{};{}H{:width$}",
+                    row,
+                    pane.col,
+                    entry,
+                    width = pane.width
+                ));
+            }
         }
 
-        buffer.push_str("\x1b[4;200H\x1b[32;40;4;1m< Disassembly >\x1b[0m");
+        if let Some(pane) = layout.disassembly {
+            buffer.push_str(&format!(
+                "\x1b[{};{}H\x1b[32;40;4;1m< Disassembly >\x1b[0m",
+                pane.row, pane.col
+            ));
+        }
 
-        print!("{}\x1b[45;0H", buffer);
+        write!(out, "{}\x1b[{};0H", buffer, layout.status_row).ok();
     }
 
-    pub fn print_dis(&mut self, system: &System) {
-        print!("\x1b[6;200H");
+    // Call before each instruction runs
+    pub fn trace(&mut self, system: &System) {
+        self.memory.trace(system);
+        self.sprite.trace(system);
 
-        let mut buffer = String::new();
-        let (raw_op, decoded, helper) = system.read_decode();
+        if system.smc().map_or(0, |smc| smc.len()) != self.patches {
+            self.scan_labels(system);
+        }
+    }
+
+    pub fn mnemonic(&self, system: &System, addr: u16) -> String {
+        let (_, decoded, helper) = system.decode_at(addr);
+
+        // Targets with a label show it in place of the bytes found there
+        let target = |nnn: u16| match self.label(system, nnn) {
+            Some(label) => format!("\x1b[36m{}\x1b[30m", label),
+            None => format!("\x1b[37m[{:#06x}]\x1b[30m", system.mem.read_u16(nnn)),
+        };
+
+        let (chip8e, chip8x) = (
+            system.platform == Platform::Chip8E,
+            system.platform == Platform::Chip8X,
+        );
 
-        let dis_str = match decoded {
+        match decoded {
+            (0, 0, 0xe, 0xd) if chip8e => String::from("STOP"),
+            (0, 0, 0xf, 2) if chip8e => String::from("NOP"),
+            (0, 1, 5, 1) if chip8e => String::from("WAIT \x1b[34mDT\x1b[30m"),
+            (0, 1, 8, 8) if chip8e => String::from("SKIP"),
+            (0, 2, 0xa, 0) if chip8x => String::from("BGCOL"),
+            (5, _, _, 1) if chip8e => format!(
+                "SGT \x1b[95mV{:01x}\x1b[30m \x1b[95mV{:01x}\x1b[30m",
+                helper.x, helper.y
+            ),
+            (5, _, _, 1) if chip8x => format!(
+                "ADDN \x1b[95mV{:01x}\x1b[30m \x1b[95mV{:01x}\x1b[30m",
+                helper.x, helper.y
+            ),
+            (5, _, _, 2) if chip8e => format!(
+                "LD [\x1b[33mI\x1b[30m] \x1b[95mV{:01x}\x1b[30m-\x1b[95mV{:01x}\x1b[30m",
+                helper.x, helper.y
+            ),
+            (5, _, _, 3) if chip8e => format!(
+                "LD \x1b[95mV{:01x}\x1b[30m-\x1b[95mV{:01x}\x1b[30m [\x1b[33mI\x1b[30m]",
+                helper.x, helper.y
+            ),
+            (0xb, 0xb, _, _) if chip8e => format!("JB {:#04x}", helper.nn),
+            (0xb, 0xf, _, _) if chip8e => format!("JF {:#04x}", helper.nn),
+            (0xb, _, _, n) if chip8x => format!(
+                "COL \x1b[95mV{:01x}\x1b[30m \x1b[95mV{:01x}\x1b[30m {:#03x}",
+                helper.x, helper.y, n
+            ),
+            (0xe, _, 0xf, 2) if chip8x => format!("SKP2 \x1b[95mV{:01x}\x1b[30m", helper.x),
+            (0xe, _, 0xf, 5) if chip8x => format!("SKNP2 \x1b[95mV{:01x}\x1b[30m", helper.x),
+            (0xf, _, 0, 3) if chip8e => format!("OUT \x1b[95mV{:01x}\x1b[30m", helper.x),
+            (0xf, _, 1, 0xb) if chip8e => format!("SKIP \x1b[95mV{:01x}\x1b[30m", helper.x),
+            (0xf, _, 4, 0xf) if chip8e => format!("DELAY \x1b[95mV{:01x}\x1b[30m", helper.x),
+            (0xf, _, 0xe, 3) | (0xf, _, 0xe, 7) if chip8e => {
+                format!("IN \x1b[95mV{:01x}\x1b[30m", helper.x)
+            }
+            (0xf, _, 0xf, 8) if chip8x => format!("OUT \x1b[95mV{:01x}\x1b[30m", helper.x),
+            (0xf, _, 0xf, 0xb) if chip8x => format!("IN \x1b[95mV{:01x}\x1b[30m", helper.x),
             (0, 0, 0xe, 0) => String::from("CLS"),
             (0, 0, 0xe, 0xe) => String::from("RET"),
             (0, _, _, _) => String::from("HALT"), //format!("SYS {:#05x} \x1b[37m[{:#06x}]\x1b[30m",helper.nnn,system.mem.read_u16(helper.nnn)),
-            (0x1, _, _, _) => format!(
-                "JP {:#05x} \x1b[37m[{:#06x}]\x1b[30m",
-                helper.nnn,
-                system.mem.read_u16(helper.nnn)
-            ),
-            (0x2, _, _, _) => format!(
-                "CALL {:#05x} \x1b[37m[{:#06x}]\x1b[30m",
-                helper.nnn,
-                system.mem.read_u16(helper.nnn)
-            ),
+            (0x1, _, _, _) => format!("JP {:#05x} {}", helper.nnn, target(helper.nnn)),
+            (0x2, _, _, _) => format!("CALL {:#05x} {}", helper.nnn, target(helper.nnn)),
             (0x3, _, _, _) => format!("SE \x1b[95mV{:01x}\x1b[30m {:#04x}", helper.x, helper.nn),
             (0x4, _, _, _) => format!("SNE \x1b[95mV{:01x}\x1b[30m {:#04x}", helper.x, helper.nn),
             (0x5, _, _, _) => format!(
@@ -154,26 +395,105 @@ impl Disassembler {
             (0xf, _, 1, 8) => format!("LD \x1b[34mST\x1b[30m \x1b[95mV{:01x}\x1b[30m", helper.x),
             (0xf, _, 1, 0xe) => format!("ADD \x1b[33mI\x1b[30m \x1b[95mV{:01x}\x1b[30m", helper.x),
             (0xf, _, 2, 9) => format!("LD \x1b[33mI\x1b[30m \x1b[95mV{:01x}\x1b[30m", helper.x),
+            (0xf, _, 3, 0) => format!("LD \x1b[33mHF\x1b[30m \x1b[95mV{:01x}\x1b[30m", helper.x),
             (0xf, _, 3, 3) => format!("BCD \x1b[95mV{:01x}\x1b[30m", helper.x),
             (0xf, _, 5, 5) => format!("LD [\x1b[33mI\x1b[30m] \x1b[95mV{:01x}\x1b[30m", helper.x),
             (0xf, _, 6, 5) => format!("LD \x1b[95mV{:01x}\x1b[30m [\x1b[33mI\x1b[30m]", helper.x),
             (0xf, 0xf, 0xf, 0xf) => String::from("* \x1b[31mBREAKPOINT\x1b[30m *"),
 
             (_, _, _, _) => String::from("! \x1b[101mUNKNOWN\x1b[30m"),
-        };
+        }
+    }
 
-        if self.dis_buffer.len() >= 32 {
-            self.dis_buffer.pop_front();
+    pub fn print_memory(&mut self, system: &System, layout: &Layout, out: &mut dyn Write) {
+        if let Some(pane) = layout.memory {
+            self.memory.print(system, pane, out);
+            write!(out, "\x1b[{};0H", layout.status_row).ok();
         }
-        self.dis_buffer.push_back(format!(
-            "\x1b[0m{:#06x} | \x1b[90m({:#06x})\x1b[30m \x1b[1m{}",
-            system.pc, raw_op, dis_str
-        ));
+    }
 
-        for (i, line) in self.dis_buffer.iter().enumerate() {
-            buffer.push_str(&format!("\x1b[K{}\x1b[0m\x1b[{};200H", line, i + 7));
+    pub fn print_sprite(&self, system: &System, layout: &Layout, out: &mut dyn Write) {
+        if let Some(pane) = layout.sprite {
+            self.sprite.print(system, pane, out);
+            write!(out, "\x1b[{};0H", layout.status_row).ok();
         }
+    }
+
+    // A window of code around the selected line, a third of the way down so more of what
+    // comes next is visible, kept inside RAM near either end. Lines are blanked and clipped to
+    // the pane width so the panes beside it survive
+    pub fn print_dis(&self, system: &System, layout: &Layout, out: &mut dyn Write) {
+        let mut buffer = String::new();
+
+        if let Some(pane) = layout.disassembly {
+            let rows = pane.height - 2;
+            let selected = self.selected(system) as usize;
+            let ram = system.mem.ram.len();
 
-        print!("{}\x1b[45;0H", buffer);
+            let mut start = selected
+                .saturating_sub(rows / 3 * 2)
+                .min(ram.saturating_sub(rows * 2));
+            start += (selected - start) % 2; // Odd addresses stay in step with the selection
+
+            let mut lines = Vec::new();
+
+            // Labelled addresses take an extra line for the label
+            for addr in (start..ram - 1).step_by(2) {
+                if lines.len() >= rows {
+                    break;
+                }
+
+                let addr = addr as u16;
+
+                if let Some(label) = self.label(system, addr) {
+                    lines.push(format!("  \x1b[36m{}:\x1b[0m", label));
+                }
+
+                let marker = if system.breakpoints().contains(&addr) {
+                    "\x1b[91m*"
+                } else if system.smc().is_some_and(|smc| smc.is_patched(addr)) {
+                    "\x1b[95m~"
+                } else {
+                    " "
+                };
+
+                let cursor = if self.selected.is_some() && addr as usize == selected {
+                    ">"
+                } else {
+                    " "
+                };
+
+                let highlight = if addr == system.pc { "\x1b[103m" } else { "" };
+
+                lines.push(format!(
+                    "{}\x1b[0m{}{}{:#06x} | \x1b[90m({:#06x})\x1b[30m \x1b[1m{}\x1b[0m",
+                    marker,
+                    cursor,
+                    highlight,
+                    addr,
+                    system.mem.read_u16(addr),
+                    self.mnemonic(system, addr)
+                ));
+            }
+
+            lines.resize(rows, String::new());
+
+            for (i, line) in lines.iter().take(rows).enumerate() {
+                let row = pane.row + 2 + i;
+
+                buffer.push_str(&format!(
+                    "\x1b[{};{}H{:width$}\x1b[{};{}H{}\x1b[0m",
+                    row,
+                    pane.col,
+                    "",
+                    row,
+                    pane.col,
+                    clip_escaped(line, pane.width),
+                    width = pane.width
+                ));
+            }
+
+            write!(out, "{}\x1b[{};0H", buffer, layout.status_row).ok();
+        }
     }
 }
@@ -2,44 +2,407 @@
 
 // Copyright (c) 2021 AnonymousDapper
 
-pub const DISPLAY_HEIGHT: usize = 32;
-pub const DISPLAY_WIDTH: usize = 64;
+use std::io::{stdout, Write};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
 
-pub fn init<S: std::fmt::Display>(name: S) {
-    let mut buffer = String::new();
-    print!("{}\x1b[2J", termion::cursor::Hide);
+use super::layout::Layout;
+use super::palette::{Colour, Palette};
+use super::vp590::ColourMap;
 
-    let header = &format!("{}\x1b[1B\x1b[0G", "█".repeat((DISPLAY_WIDTH * 2) + 4));
-    let buf = &format!("\x1b[7C██\x1b[{}C██\x1b[1B\x1b[0G", (DISPLAY_WIDTH * 2));
+pub use super::{DISPLAY_HEIGHT, DISPLAY_WIDTH};
 
-    buffer.push_str(&format!(
-        "\x1b[3;56HRusty-8 - CHIP-8 Emulator ({})\x1b[4;8H",
-        name
-    ));
-    buffer.push_str(header);
-    for _ in 0..DISPLAY_HEIGHT {
-        buffer.push_str(buf);
+const FRAME_TIME: Duration = Duration::from_micros(16_667);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Renderer {
+    Block,     // 1x1 pixels per cell, two columns wide
+    HalfBlock, // 1x2 pixels per cell
+    Braille,   // 2x4 pixels per cell
+    Ascii,     // 1x1 pixels per cell, no colour
+}
+
+impl Renderer {
+    pub const NAMES: &'static [&'static str] = &["block", "half", "braille", "ascii"];
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "block" => Some(Self::Block),
+            "half" => Some(Self::HalfBlock),
+            "braille" => Some(Self::Braille),
+            "ascii" => Some(Self::Ascii),
+            _ => None,
+        }
+    }
+
+    // Number of terminal columns and rows needed to draw a `width` x `height` display
+    pub fn size(&self, width: usize, height: usize) -> (usize, usize) {
+        match self {
+            Self::Block => (width * 2, height),
+            Self::HalfBlock => (width, height.div_ceil(2)),
+            Self::Braille => (width.div_ceil(2), height.div_ceil(4)),
+            Self::Ascii => (width, height),
+        }
+    }
+
+    pub fn side_width(&self) -> usize {
+        self.border().1.chars().count()
     }
 
-    buffer.push_str("\x1b[7C");
-    buffer.push_str(header);
+    fn border(&self) -> (&'static str, &'static str, &'static str) {
+        // (horizontal edge, vertical edge, corner)
+        match self {
+            Self::Block => ("█", "██", "██"),
+            Self::HalfBlock | Self::Braille => ("█", "█", "█"),
+            Self::Ascii => ("-", "|", "+"),
+        }
+    }
+}
 
-    print!("{}\x1b[45;0H", buffer);
+// Draws to stdout unless given another output, nothing is shared between screens
+pub struct Screen {
+    title: String,
+    renderer: Renderer,
+    debug: bool,
+    term_size: Option<(usize, usize)>, // Laid out for this size instead of the terminal's
+    layout: Layout,
+    resized: Arc<AtomicBool>,
+    interrupted: Arc<AtomicBool>,
+    out: Box<dyn Write + Send>,
+    palette: Palette,
+    persistence: u8, // Frames a cleared pixel takes to fade out, 0 disables phosphor
+    previous: Vec<u8>, // Pixel values from the last draw
+    fade: Vec<(u8, u8)>, // (frames left, value that was cleared) per pixel
+    last_fade: Instant,
+    colour_map: Option<ColourMap>,
 }
 
-pub fn write_display(buffer: &[u8]) {
-    let mut display = String::new();
-    print!("\x1b[5;10H");
+impl Screen {
+    pub fn new<S: std::fmt::Display>(name: S, renderer: Renderer, debug: bool) -> Self {
+        Self {
+            title: format!("Rusty-8 - CHIP-8 Emulator ({})", name),
+            renderer,
+            debug,
+            term_size: None,
+            layout: Layout::new(renderer, debug),
+            resized: Arc::new(AtomicBool::new(false)),
+            interrupted: Arc::new(AtomicBool::new(false)),
+            out: Box::new(stdout()),
+            palette: Palette::default(),
+            persistence: 0,
+            previous: vec![0; DISPLAY_WIDTH * DISPLAY_HEIGHT],
+            fade: vec![(0, 0); DISPLAY_WIDTH * DISPLAY_HEIGHT],
+            last_fade: Instant::now(),
+            colour_map: None,
+        }
+    }
+
+    pub fn set_output(&mut self, out: Box<dyn Write + Send>) {
+        self.out = out;
+    }
+
+    pub fn output(&mut self) -> &mut dyn Write {
+        &mut self.out
+    }
+
+    // Lays out for `size` from now on, rather than whatever terminal the process has
+    pub fn set_term_size(&mut self, size: (usize, usize)) {
+        self.term_size = Some(size);
+        self.layout = Layout::compute(size, self.renderer, self.debug);
+    }
+
+    // Setting this, usually from a SIGWINCH handler, lays the screen out again
+    pub fn resize_flag(&self) -> Arc<AtomicBool> {
+        Arc::clone(&self.resized)
+    }
+
+    // Setting this, usually from a SIGINT handler, stops the run loop
+    pub fn interrupt_flag(&self) -> Arc<AtomicBool> {
+        Arc::clone(&self.interrupted)
+    }
+
+    // Whether the interrupt flag was set since the last call
+    pub fn take_interrupt(&self) -> bool {
+        self.interrupted.swap(false, Ordering::Relaxed)
+    }
+
+    pub fn set_palette(&mut self, palette: Palette) {
+        self.palette = palette;
+    }
+
+    pub fn set_phosphor(&mut self, frames: u8) {
+        self.persistence = frames;
+    }
+
+    // Overrides the palette with CHIP-8X colours, picked up on the next `write_display`
+    pub fn set_colour_map(&mut self, map: Option<&ColourMap>) {
+        self.colour_map = map.cloned();
+    }
+
+    pub fn layout(&self) -> &Layout {
+        &self.layout
+    }
+
+    // Recomputes the layout and redraws the frame if the terminal was resized since the last call
+    pub fn check_resize(&mut self) -> bool {
+        if self.resized.swap(false, Ordering::Relaxed) {
+            self.layout = match self.term_size {
+                Some(size) => Layout::compute(size, self.renderer, self.debug),
+                None => Layout::new(self.renderer, self.debug),
+            };
+            self.init();
+            true
+        } else {
+            false
+        }
+    }
+
+    pub fn init(&mut self) {
+        let mut buffer = String::new();
+
+        // Autowrap is disabled so panes clipped by the terminal edge can't spill onto the next line
+        write!(self.out, "{}\x1b[?7l\x1b[2J", termion::cursor::Hide).ok();
+
+        let frame = self.layout.frame;
+        let (edge, side, corner) = self.renderer.border();
+        let corner_width = corner.chars().count();
+        let inner_width = frame.width - self.renderer.side_width() * 2;
+
+        let header = &format!(
+            "{}{}{}",
+            corner,
+            edge.repeat(frame.width - corner_width * 2),
+            corner
+        );
 
-    for (y, line) in buffer.chunks_exact(DISPLAY_WIDTH).enumerate() {
-        display.push_str(&format!("\x1b[{};10H", y + 5));
-        for (_x, pixel) in line.iter().enumerate() {
-            display.push_str(&format!(
-                "\x1b[{}m██\x1b[0m",
-                if pixel & 0x1 == 1 { "97" } else { "30" }
+        let title_len = self.title.chars().count();
+        let title_col = (frame.col + frame.width / 2).saturating_sub(title_len / 2);
+
+        buffer.push_str(&format!(
+            "\x1b[{};{}H{}",
+            self.layout.title_row,
+            title_col.max(1),
+            self.title
+        ));
+
+        buffer.push_str(&format!("\x1b[{};{}H{}", frame.row, frame.col, header));
+        for row in frame.row + 1..frame.bottom() - 1 {
+            buffer.push_str(&format!(
+                "\x1b[{};{}H{}\x1b[{}C{}",
+                row, frame.col, side, inner_width, side
             ));
         }
+        buffer.push_str(&format!(
+            "\x1b[{};{}H{}",
+            frame.bottom() - 1,
+            frame.col,
+            header
+        ));
+
+        write!(self.out, "{}\x1b[{};0H", buffer, self.layout.status_row).ok();
+    }
+
+    // Advances phosphor fading at 60Hz, redrawing while any pixel is still fading
+    pub fn update(&mut self, buffer: &[u8]) {
+        if self.persistence == 0 || self.last_fade.elapsed() < FRAME_TIME {
+            return;
+        }
+
+        self.last_fade = Instant::now();
+
+        let mut fading = false;
+        for (frames, _) in self.fade.iter_mut().filter(|(frames, _)| *frames > 0) {
+            *frames -= 1;
+            fading = true;
+        }
+
+        if fading {
+            self.write_display(buffer);
+        }
+    }
+
+    pub fn write_display(&mut self, buffer: &[u8]) {
+        if self.persistence > 0 {
+            for (i, (&value, previous)) in buffer.iter().zip(self.previous.iter_mut()).enumerate() {
+                if value == 0 && *previous != 0 {
+                    self.fade[i] = (self.persistence, *previous);
+                } else if value != 0 {
+                    self.fade[i] = (0, 0);
+                }
+
+                *previous = value;
+            }
+        }
+
+        let mut display = String::new();
+        let area = self.layout.display;
+        let background = self.background();
+
+        for row in 0..area.height {
+            display.push_str(&format!("\x1b[{};{}H", area.row + row, area.col));
+
+            match self.renderer {
+                Renderer::Block => {
+                    for x in 0..DISPLAY_WIDTH {
+                        display.push_str(&format!(
+                            "\x1b[{}m██\x1b[0m",
+                            self.colour(buffer, x, row).fg()
+                        ));
+                    }
+                }
+                Renderer::HalfBlock => {
+                    // Upper pixel is the foreground, lower pixel the background
+                    for x in 0..area.width {
+                        display.push_str(&format!(
+                            "\x1b[{};{}m▀",
+                            self.colour(buffer, x, row * 2).fg(),
+                            self.colour(buffer, x, row * 2 + 1).bg()
+                        ));
+                    }
+                    display.push_str("\x1b[0m");
+                }
+                Renderer::Braille => {
+                    // A cell can only have one colour, so the brightest dot wins
+                    for x in 0..area.width {
+                        let (cell, colour) = self.braille_cell(buffer, x * 2, row * 4);
+                        display.push_str(&format!(
+                            "\x1b[{};{}m{}",
+                            colour.fg(),
+                            background.bg(),
+                            cell
+                        ));
+                    }
+                    display.push_str("\x1b[0m");
+                }
+                Renderer::Ascii => {
+                    for x in 0..area.width {
+                        display.push(match self.level(buffer, x, row) {
+                            Level::On(_) => '#',
+                            Level::Fading(..) => '+',
+                            Level::Off => ' ',
+                        });
+                    }
+                }
+            }
+        }
+
+        write!(self.out, "{}\x1b[{};0H", display, self.layout.status_row).ok();
+    }
+
+    pub fn write_status(&mut self, status: &str) {
+        write!(
+            self.out,
+            "\x1b[{};1H\x1b[2K{}\x1b[{};0H",
+            self.layout.status_row, status, self.layout.status_row
+        )
+        .ok();
+    }
+
+    fn level(&self, buffer: &[u8], x: usize, y: usize) -> Level {
+        if x >= DISPLAY_WIDTH || y >= DISPLAY_HEIGHT {
+            return Level::Off;
+        }
+
+        let i = x + y * DISPLAY_WIDTH;
+        let (frames, value) = self.fade[i];
+
+        if buffer[i] != 0 {
+            Level::On(buffer[i])
+        } else if frames > 0 {
+            Level::Fading(value, frames)
+        } else {
+            Level::Off
+        }
+    }
+
+    fn colour(&self, buffer: &[u8], x: usize, y: usize) -> Colour {
+        self.colour_of(self.level(buffer, x, y), x, y)
     }
 
-    print!("{}\x1b[45;0H", display);
+    fn colour_of(&self, level: Level, x: usize, y: usize) -> Colour {
+        let background = self.background();
+
+        match level {
+            Level::On(value) => self.pixel(value, x, y),
+            Level::Fading(value, frames) => {
+                let amount = (frames as u16 * 255 / (self.persistence as u16 + 1)) as u8;
+                background.blend(self.pixel(value, x, y), amount)
+            }
+            Level::Off => background,
+        }
+    }
+
+    fn background(&self) -> Colour {
+        match self.colour_map {
+            Some(ref map) => {
+                let (r, g, b) = map.background();
+                Colour::Rgb(r, g, b)
+            }
+            None => self.palette.background(),
+        }
+    }
+
+    fn pixel(&self, value: u8, x: usize, y: usize) -> Colour {
+        match self.colour_map {
+            Some(ref map) => {
+                let (r, g, b) = map.foreground(x, y);
+                Colour::Rgb(r, g, b)
+            }
+            None => self.palette.pixel(value),
+        }
+    }
+
+    fn braille_cell(&self, buffer: &[u8], x: usize, y: usize) -> (char, Colour) {
+        // Braille dot numbering runs down the left column first, with the bottom row added last
+        const DOTS: [(usize, usize, u32); 8] = [
+            (0, 0, 0x01),
+            (0, 1, 0x02),
+            (0, 2, 0x04),
+            (1, 0, 0x08),
+            (1, 1, 0x10),
+            (1, 2, 0x20),
+            (0, 3, 0x40),
+            (1, 3, 0x80),
+        ];
+
+        let mut bits = 0;
+        let mut brightest = Level::Off;
+
+        for (dx, dy, bit) in DOTS.iter() {
+            let level = self.level(buffer, x + dx, y + dy);
+
+            if level != Level::Off {
+                bits |= bit;
+            }
+
+            if level.brightness() > brightest.brightness() {
+                brightest = level;
+            }
+        }
+
+        let colour = match brightest {
+            Level::Off => self.pixel(1, x, y),
+            _ => self.colour_of(brightest, x, y),
+        };
+
+        (std::char::from_u32(0x2800 + bits).unwrap(), colour)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Level {
+    On(u8),
+    Fading(u8, u8), // (value, frames left)
+    Off,
+}
+
+impl Level {
+    fn brightness(&self) -> u16 {
+        match self {
+            Self::On(_) => u16::MAX,
+            Self::Fading(_, frames) => *frames as u16,
+            Self::Off => 0,
+        }
+    }
 }
@@ -0,0 +1,131 @@
+// The MIT License (MIT)
+
+// Copyright (c) 2021 AnonymousDapper
+
+use super::compare::Compare;
+use super::rom::RomError;
+use super::{GuestError, System};
+
+// A byte of RAM whose change between steps counts towards the reward, like a score
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct RewardTerm {
+    addr: u16,
+    weight: f64,
+}
+
+// The episode is over once the byte at `addr` compares true against `value`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct DoneTerm {
+    addr: u16,
+    compare: Compare,
+    value: u8,
+}
+
+// A game as a reinforcement learning environment. Each step holds the action's keys down for a
+// few frames and hands back the display, the reward and whether the episode is over. Nothing is
+// shared between environments, so as many as needed can run side by side on their own threads
+#[derive(Debug, Clone)]
+pub struct Environment {
+    start: System, // Snapshot taken after loading, `reset` goes back to it
+    system: System,
+    frame_skip: usize,
+    rewards: Vec<RewardTerm>,
+    done: Vec<DoneTerm>,
+}
+
+impl Environment {
+    pub fn new(mut system: System, rom: &[u8]) -> Result<Self, RomError> {
+        system.load_rom(rom)?;
+
+        Ok(Self {
+            start: system.clone(),
+            system,
+            frame_skip: 1,
+            rewards: Vec::new(),
+            done: Vec::new(),
+        })
+    }
+
+    pub fn system(&self) -> &System {
+        &self.system
+    }
+
+    pub fn frame_skip(&self) -> usize {
+        self.frame_skip
+    }
+
+    // Frames each step runs for, with the same keys held
+    pub fn set_frame_skip(&mut self, frames: usize) {
+        self.frame_skip = frames.max(1);
+    }
+
+    // Each step is rewarded `weight` times how much the byte at `addr` went up. The change is
+    // taken as a signed byte, so a score wrapping from 255 to 0 counts as going up by 1
+    pub fn add_reward(&mut self, addr: u16, weight: f64) {
+        self.rewards.push(RewardTerm { addr, weight });
+    }
+
+    // Any matching condition ends the episode, as does the program halting
+    pub fn add_done(&mut self, addr: u16, compare: Compare, value: u8) {
+        self.done.push(DoneTerm {
+            addr,
+            compare,
+            value,
+        });
+    }
+
+    pub fn observation(&self) -> Vec<u8> {
+        self.system.display().to_vec()
+    }
+
+    pub fn is_done(&self) -> bool {
+        self.system.is_halted()
+            || self.done.iter().any(|term| {
+                let byte = self.system.memory().read_u8(term.addr);
+                term.compare.test(byte as u32, term.value as u32)
+            })
+    }
+
+    // Why the program stopped, if it faulted. The step that hit it comes back as done
+    pub fn error(&self) -> Option<GuestError> {
+        self.system.error()
+    }
+
+    pub fn reset(&mut self) -> Vec<u8> {
+        self.system = self.start.clone();
+        self.observation()
+    }
+
+    // `keys` are held down for the whole step and the rest let go
+    pub fn step(&mut self, keys: &[u8]) -> (Vec<u8>, f64, bool) {
+        let before: Vec<u8> = self
+            .rewards
+            .iter()
+            .map(|term| self.system.memory().read_u8(term.addr))
+            .collect();
+
+        for key in 0..16 {
+            self.system.set_key(key, keys.contains(&key));
+        }
+
+        for _ in 0..self.frame_skip {
+            if self.is_done() {
+                break;
+            }
+
+            self.system.run_frame();
+        }
+
+        let reward = self
+            .rewards
+            .iter()
+            .zip(before)
+            .map(|(term, before)| {
+                let after = self.system.memory().read_u8(term.addr);
+                term.weight * after.wrapping_sub(before) as i8 as f64
+            })
+            .sum();
+
+        (self.observation(), reward, self.is_done())
+    }
+}
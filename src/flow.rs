@@ -0,0 +1,311 @@
+// The MIT License (MIT)
+
+// Copyright (c) 2021 AnonymousDapper
+
+use std::collections::{BTreeMap, BTreeSet, VecDeque};
+use std::fmt::Write;
+
+use super::platform::Platform;
+use super::System;
+
+// How an instruction passes on control
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Flow {
+    next: Vec<u16>,
+    call: Option<u16>,
+    ends_block: bool,
+    unresolved: bool, // Jumps somewhere only known at run time
+}
+
+fn flow(system: &System, addr: u16) -> Flow {
+    let (_, decoded, helper) = system.decode_at(addr);
+    let (chip8e, chip8x) = (
+        system.platform == Platform::Chip8E,
+        system.platform == Platform::Chip8X,
+    );
+
+    let after = addr.wrapping_add(2);
+    let skip = vec![after, addr.wrapping_add(4)];
+
+    let (next, call, ends_block, unresolved) = match decoded {
+        (0, 0, 0xe, 0xd) if chip8e => (vec![], None, true, false),
+        (0, 0, 0xf, 2) | (0, 1, 5, 1) if chip8e => (vec![after], None, false, false),
+        (0, 2, 0xa, 0) if chip8x => (vec![after], None, false, false),
+        (0, 1, 8, 8) if chip8e => (vec![addr.wrapping_add(4)], None, true, false),
+        (5, _, _, 1) if chip8e => (skip, None, true, false),
+        (0xb, 0xb, _, _) if chip8e => (
+            vec![after.wrapping_sub(helper.nn as u16)],
+            None,
+            true,
+            false,
+        ),
+        (0xb, 0xf, _, _) if chip8e => (
+            vec![after.wrapping_add(helper.nn as u16)],
+            None,
+            true,
+            false,
+        ),
+        (0xe, _, 0xf, 2) | (0xe, _, 0xf, 5) if chip8x => (skip, None, true, false),
+        (0xf, _, 1, 0xb) if chip8e => (vec![], None, true, true),
+        (0xb, _, _, _) if chip8x => (vec![after], None, false, false),
+
+        (0, 0, 0xe, 0) => (vec![after], None, false, false),
+        (0, 0, 0xe, 0xe) => (vec![], None, true, false),
+        (0, _, _, _) => (vec![], None, true, false), // Halts
+        (0x1, _, _, _) => (vec![helper.nnn], None, true, false),
+        (0x2, _, _, _) => (vec![after], Some(helper.nnn), true, false),
+        (0x3, _, _, _) | (0x4, _, _, _) | (0x5, _, _, 0) | (0x9, _, _, 0) => {
+            (skip, None, true, false)
+        }
+        (0xe, _, 9, 0xe) | (0xe, _, 0xa, 1) => (skip, None, true, false),
+        (0xb, _, _, _) => (vec![], None, true, true),
+        (0xf, 0xf, 0xf, 0xf) => (vec![], None, true, false), // Breakpoint, also halts
+        _ => (vec![after], None, false, false),
+    };
+
+    Flow {
+        next,
+        call,
+        ends_block,
+        unresolved,
+    }
+}
+
+// A run of instructions only ever entered at the top and left at the bottom
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Block {
+    pub start: u16,
+    pub end: u16, // Exclusive
+    pub successors: Vec<u16>,
+    pub calls: Vec<u16>,
+    pub unresolved: bool,
+}
+
+impl Block {
+    pub fn instructions(&self) -> impl Iterator<Item = u16> {
+        (self.start..self.end).step_by(2)
+    }
+}
+
+// The blocks reachable from an entry point without following calls
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Subroutine {
+    pub entry: u16,
+    pub blocks: Vec<u16>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FlowGraph {
+    blocks: BTreeMap<u16, Block>,
+    subroutines: Vec<Subroutine>,
+    outside: BTreeSet<u16>, // Targets outside the ROM, which aren't followed
+}
+
+impl FlowGraph {
+    // Follows every path from the entry point through the loaded ROM. Only code that can be
+    // reached statically is found, anything behind an unresolved jump is left out
+    pub fn build(system: &System) -> Self {
+        let start = system.load_address as usize;
+        let rom = start..start + system.rom.len();
+
+        let mut flows = BTreeMap::new();
+        let mut leaders = BTreeSet::new();
+        let mut entries = vec![system.entry_address];
+        let mut outside = BTreeSet::new();
+        let mut work = VecDeque::from([system.entry_address]);
+
+        leaders.insert(system.entry_address);
+
+        while let Some(addr) = work.pop_front() {
+            if flows.contains_key(&addr) {
+                continue;
+            }
+
+            // Half an instruction hanging off the end doesn't count
+            if !rom.contains(&(addr as usize)) || !rom.contains(&(addr as usize + 1)) {
+                outside.insert(addr);
+                continue;
+            }
+
+            let flow = flow(system, addr);
+
+            if let Some(target) = flow.call {
+                if !entries.contains(&target) {
+                    entries.push(target);
+                }
+                leaders.insert(target);
+                work.push_back(target);
+            }
+
+            for &next in flow.next.iter() {
+                if flow.ends_block {
+                    leaders.insert(next);
+                }
+                work.push_back(next);
+            }
+
+            flows.insert(addr, flow);
+        }
+
+        // Instructions are grouped until a leader, a block ending instruction or a gap
+        let mut blocks = BTreeMap::new();
+        let mut current: Option<Block> = None;
+
+        for (&addr, flow) in flows.iter() {
+            let mut block = match current.take() {
+                Some(block) if block.end == addr && !leaders.contains(&addr) => block,
+                previous => {
+                    if let Some(mut block) = previous {
+                        block.successors.push(block.end);
+                        blocks.insert(block.start, block);
+                    }
+
+                    Block {
+                        start: addr,
+                        end: addr,
+                        successors: Vec::new(),
+                        calls: Vec::new(),
+                        unresolved: false,
+                    }
+                }
+            };
+
+            block.end = addr.wrapping_add(2);
+            block.calls.extend(flow.call);
+            block.unresolved |= flow.unresolved;
+
+            if flow.ends_block {
+                block.successors = flow.next.clone();
+                blocks.insert(block.start, block);
+            } else {
+                current = Some(block);
+            }
+        }
+
+        if let Some(mut block) = current {
+            block.successors.push(block.end);
+            blocks.insert(block.start, block);
+        }
+
+        // Each block belongs to the first subroutine that reaches it
+        let mut owned = BTreeSet::new();
+        let mut subroutines = Vec::new();
+
+        for entry in entries {
+            let mut sub_blocks = Vec::new();
+            let mut work = vec![entry];
+
+            while let Some(start) = work.pop() {
+                if !blocks.contains_key(&start) || !owned.insert(start) {
+                    continue;
+                }
+
+                sub_blocks.push(start);
+                work.extend(blocks[&start].successors.iter().rev());
+            }
+
+            sub_blocks.sort_unstable();
+            subroutines.push(Subroutine {
+                entry,
+                blocks: sub_blocks,
+            });
+        }
+
+        Self {
+            blocks,
+            subroutines,
+            outside,
+        }
+    }
+
+    pub fn blocks(&self) -> impl Iterator<Item = &Block> {
+        self.blocks.values()
+    }
+
+    pub fn block(&self, start: u16) -> Option<&Block> {
+        self.blocks.get(&start)
+    }
+
+    pub fn subroutines(&self) -> &[Subroutine] {
+        &self.subroutines
+    }
+
+    // Graphviz DOT with a cluster per subroutine, `text` gives each instruction's disassembly
+    pub fn to_dot<F: Fn(u16) -> String>(&self, system: &System, text: F) -> String {
+        let escape = |s: String| s.replace('\\', "\\\\").replace('"', "\\\"");
+        let mut dot =
+            String::from("digraph cfg {\n    node [shape=box, fontname=\"monospace\"];\n");
+
+        for (i, sub) in self.subroutines.iter().enumerate() {
+            let name = match system.symbols().name(sub.entry) {
+                Some(name) => name.to_string(),
+                None if sub.entry == system.entry_address => String::from("entry"),
+                None => format!("sub_{:03x}", sub.entry),
+            };
+
+            writeln!(dot, "    subgraph cluster_{} {{", i).ok();
+            writeln!(dot, "        label=\"{}\";", escape(name)).ok();
+
+            for block in sub.blocks.iter().map(|start| &self.blocks[start]) {
+                let mut label = String::new();
+
+                for addr in block.instructions() {
+                    write!(label, "{:#06x}  {}\\l", addr, escape(text(addr))).ok();
+                }
+
+                let colour = if block.unresolved { ", color=red" } else { "" };
+
+                writeln!(
+                    dot,
+                    "        b_{:03x} [label=\"{}\"{}];",
+                    block.start, label, colour
+                )
+                .ok();
+            }
+
+            writeln!(dot, "    }}").ok();
+        }
+
+        for addr in self.outside.iter() {
+            writeln!(
+                dot,
+                "    b_{:03x} [label=\"{:#06x} outside the ROM\", shape=ellipse, style=dashed];",
+                addr, addr
+            )
+            .ok();
+        }
+
+        for block in self.blocks.values() {
+            for next in block.successors.iter() {
+                writeln!(dot, "    b_{:03x} -> b_{:03x};", block.start, next).ok();
+            }
+
+            for call in block.calls.iter() {
+                writeln!(
+                    dot,
+                    "    b_{:03x} -> b_{:03x} [style=dashed, label=\"call\"];",
+                    block.start, call
+                )
+                .ok();
+            }
+
+            if block.unresolved {
+                writeln!(
+                    dot,
+                    "    u_{:03x} [label=\"unresolved\", shape=octagon, color=red];",
+                    block.start
+                )
+                .ok();
+                writeln!(
+                    dot,
+                    "    b_{:03x} -> u_{:03x} [style=dashed, color=red];",
+                    block.start, block.start
+                )
+                .ok();
+            }
+        }
+
+        dot.push_str("}\n");
+        dot
+    }
+}
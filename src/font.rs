@@ -0,0 +1,217 @@
+// The MIT License (MIT)
+
+// Copyright (c) 2021 AnonymousDapper
+
+pub const SMALL_GLYPH_SIZE: usize = 5;
+pub const BIG_GLYPH_SIZE: usize = 10;
+
+const SMALL_FONT_SIZE: usize = SMALL_GLYPH_SIZE * 16;
+const BIG_FONT_SIZE: usize = BIG_GLYPH_SIZE * 16;
+
+#[rustfmt::skip]
+static VIP_FONT: [u8; SMALL_FONT_SIZE] = [
+    0xF0, 0x90, 0x90, 0x90, 0xF0,
+    0x60, 0x20, 0x20, 0x20, 0x70,
+    0xF0, 0x10, 0xF0, 0x80, 0xF0,
+    0xF0, 0x10, 0xF0, 0x10, 0xF0,
+    0xA0, 0xA0, 0xF0, 0x20, 0x20,
+    0xF0, 0x80, 0xF0, 0x10, 0xF0,
+    0xF0, 0x80, 0xF0, 0x90, 0xF0,
+    0xF0, 0x10, 0x10, 0x10, 0x10,
+    0xF0, 0x90, 0xF0, 0x90, 0xF0,
+    0xF0, 0x90, 0xF0, 0x10, 0xF0,
+    0xF0, 0x90, 0xF0, 0x90, 0x90,
+    0xF0, 0x50, 0x70, 0x50, 0xF0,
+    0xF0, 0x80, 0x80, 0x80, 0xF0,
+    0xF0, 0x50, 0x50, 0x50, 0xF0,
+    0xF0, 0x80, 0xF0, 0x80, 0xF0,
+    0xF0, 0x80, 0xF0, 0x80, 0x80,
+];
+
+#[rustfmt::skip]
+static DREAM_6800_FONT: [u8; SMALL_FONT_SIZE] = [
+    0xE0, 0xA0, 0xA0, 0xA0, 0xE0,
+    0x40, 0x40, 0x40, 0x40, 0x40,
+    0xE0, 0x20, 0xE0, 0x80, 0xE0,
+    0xE0, 0x20, 0xE0, 0x20, 0xE0,
+    0x80, 0xA0, 0xA0, 0xE0, 0x20,
+    0xE0, 0x80, 0xE0, 0x20, 0xE0,
+    0xE0, 0x80, 0xE0, 0xA0, 0xE0,
+    0xE0, 0x20, 0x20, 0x20, 0x20,
+    0xE0, 0xA0, 0xE0, 0xA0, 0xE0,
+    0xE0, 0xA0, 0xE0, 0x20, 0xE0,
+    0xE0, 0xA0, 0xE0, 0xA0, 0xA0,
+    0xC0, 0xA0, 0xE0, 0xA0, 0xC0,
+    0xE0, 0x80, 0x80, 0x80, 0xE0,
+    0xC0, 0xA0, 0xA0, 0xA0, 0xC0,
+    0xE0, 0x80, 0xE0, 0x80, 0xE0,
+    0xE0, 0x80, 0xC0, 0x80, 0x80,
+];
+
+#[rustfmt::skip]
+static ETI_660_FONT: [u8; SMALL_FONT_SIZE] = [
+    0xE0, 0xA0, 0xA0, 0xA0, 0xE0,
+    0x20, 0x20, 0x20, 0x20, 0x20,
+    0xE0, 0x20, 0xE0, 0x80, 0xE0,
+    0xE0, 0x20, 0xE0, 0x20, 0xE0,
+    0xA0, 0xA0, 0xE0, 0x20, 0x20,
+    0xE0, 0x80, 0xE0, 0x20, 0xE0,
+    0xE0, 0x80, 0xE0, 0xA0, 0xE0,
+    0xE0, 0x20, 0x20, 0x20, 0x20,
+    0xE0, 0xA0, 0xE0, 0xA0, 0xE0,
+    0xE0, 0xA0, 0xE0, 0x20, 0xE0,
+    0xE0, 0xA0, 0xE0, 0xA0, 0xA0,
+    0x80, 0x80, 0xE0, 0xA0, 0xE0,
+    0xE0, 0x80, 0x80, 0x80, 0xE0,
+    0x20, 0x20, 0xE0, 0xA0, 0xE0,
+    0xE0, 0x80, 0xE0, 0x80, 0xE0,
+    0xE0, 0x80, 0xC0, 0x80, 0x80,
+];
+
+#[rustfmt::skip]
+static FISH_N_CHIPS_FONT: [u8; SMALL_FONT_SIZE] = [
+    0x60, 0xA0, 0xA0, 0xA0, 0xC0,
+    0x40, 0xC0, 0x40, 0x40, 0xE0,
+    0xC0, 0x20, 0x40, 0x80, 0xE0,
+    0xC0, 0x20, 0x40, 0x20, 0xC0,
+    0x20, 0xA0, 0xE0, 0x20, 0x20,
+    0xE0, 0x80, 0xC0, 0x20, 0xC0,
+    0x40, 0x80, 0xC0, 0xA0, 0x40,
+    0xE0, 0x20, 0x60, 0x40, 0x40,
+    0x40, 0xA0, 0x40, 0xA0, 0x40,
+    0x40, 0xA0, 0x60, 0x20, 0x40,
+    0x40, 0xA0, 0xE0, 0xA0, 0xA0,
+    0xC0, 0xA0, 0xC0, 0xA0, 0xC0,
+    0x60, 0x80, 0x80, 0x80, 0x60,
+    0xC0, 0xA0, 0xA0, 0xA0, 0xC0,
+    0xE0, 0x80, 0xC0, 0x80, 0xE0,
+    0xE0, 0x80, 0xC0, 0x80, 0x80,
+];
+
+// Also what most modern interpreters use for CHIP-8
+#[rustfmt::skip]
+static SCHIP_FONT: [u8; SMALL_FONT_SIZE] = [
+    0xF0, 0x90, 0x90, 0x90, 0xF0,
+    0x20, 0x60, 0x20, 0x20, 0x70,
+    0xF0, 0x10, 0xF0, 0x80, 0xF0,
+    0xF0, 0x10, 0xF0, 0x10, 0xF0,
+    0x90, 0x90, 0xF0, 0x10, 0x10,
+    0xF0, 0x80, 0xF0, 0x10, 0xF0,
+    0xF0, 0x80, 0xF0, 0x90, 0xF0,
+    0xF0, 0x10, 0x20, 0x40, 0x40,
+    0xF0, 0x90, 0xF0, 0x90, 0xF0,
+    0xF0, 0x90, 0xF0, 0x10, 0xF0,
+    0xF0, 0x90, 0xF0, 0x90, 0x90,
+    0xE0, 0x90, 0xE0, 0x90, 0xE0,
+    0xF0, 0x80, 0x80, 0x80, 0xF0,
+    0xE0, 0x90, 0x90, 0x90, 0xE0,
+    0xF0, 0x80, 0xF0, 0x80, 0xF0,
+    0xF0, 0x80, 0xF0, 0x80, 0x80,
+];
+
+// 8x10 digits for Fx30, SCHIP 1.1 only had 0-9 so A-F follow XO-CHIP
+#[rustfmt::skip]
+static SCHIP_BIG_FONT: [u8; BIG_FONT_SIZE] = [
+    0x3C, 0x7E, 0xE7, 0xC3, 0xC3, 0xC3, 0xC3, 0xE7, 0x7E, 0x3C,
+    0x18, 0x38, 0x58, 0x18, 0x18, 0x18, 0x18, 0x18, 0x18, 0x3C,
+    0x3E, 0x7F, 0xC3, 0x06, 0x0C, 0x18, 0x30, 0x60, 0xFF, 0xFF,
+    0x3C, 0x7E, 0xC3, 0x03, 0x0E, 0x0E, 0x03, 0xC3, 0x7E, 0x3C,
+    0x06, 0x0E, 0x1E, 0x36, 0x66, 0xC6, 0xFF, 0xFF, 0x06, 0x06,
+    0xFF, 0xFF, 0xC0, 0xC0, 0xFC, 0xFE, 0x03, 0xC3, 0x7E, 0x3C,
+    0x3E, 0x7C, 0xC0, 0xC0, 0xFC, 0xFE, 0xC3, 0xC3, 0x7E, 0x3C,
+    0xFF, 0xFF, 0x03, 0x06, 0x0C, 0x18, 0x30, 0x60, 0x60, 0x60,
+    0x3C, 0x7E, 0xC3, 0xC3, 0x7E, 0x7E, 0xC3, 0xC3, 0x7E, 0x3C,
+    0x3C, 0x7E, 0xC3, 0xC3, 0x7F, 0x3F, 0x03, 0x03, 0x3E, 0x7C,
+    0x7E, 0xFF, 0xC3, 0xC3, 0xC3, 0xFF, 0xFF, 0xC3, 0xC3, 0xC3,
+    0xFC, 0xFC, 0xC3, 0xC3, 0xFC, 0xFC, 0xC3, 0xC3, 0xFC, 0xFC,
+    0x3C, 0xFF, 0xC3, 0xC0, 0xC0, 0xC0, 0xC0, 0xC3, 0xFF, 0x3C,
+    0xFC, 0xFE, 0xC3, 0xC3, 0xC3, 0xC3, 0xC3, 0xC3, 0xFE, 0xFC,
+    0xFF, 0xFF, 0xC0, 0xC0, 0xFF, 0xFF, 0xC0, 0xC0, 0xFF, 0xFF,
+    0xFF, 0xFF, 0xC0, 0xC0, 0xFF, 0xFF, 0xC0, 0xC0, 0xC0, 0xC0,
+];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FontSet {
+    Vip,
+    Dream6800,
+    Eti660,
+    FishNChips,
+    Schip,
+}
+
+impl FontSet {
+    pub const NAMES: &'static [&'static str] = &["vip", "dream6800", "eti660", "fish", "schip"];
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "vip" => Some(Self::Vip),
+            "dream6800" => Some(Self::Dream6800),
+            "eti660" => Some(Self::Eti660),
+            "fish" => Some(Self::FishNChips),
+            "schip" => Some(Self::Schip),
+            _ => None,
+        }
+    }
+
+    // Every set gets the big font, interpreters that never had Fx30 just won't use it
+    pub fn font(&self) -> Font {
+        let small = match self {
+            Self::Vip => &VIP_FONT,
+            Self::Dream6800 => &DREAM_6800_FONT,
+            Self::Eti660 => &ETI_660_FONT,
+            Self::FishNChips => &FISH_N_CHIPS_FONT,
+            Self::Schip => &SCHIP_FONT,
+        };
+
+        Font {
+            small: small.to_vec(),
+            big: SCHIP_BIG_FONT.to_vec(),
+        }
+    }
+}
+
+// Small 4x5 hex digits for Fx29, followed in memory by the 8x10 ones for Fx30
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Font {
+    small: Vec<u8>,
+    big: Vec<u8>,
+}
+
+impl Font {
+    // A custom font file holds the 80 small glyph bytes, optionally followed by 160 big glyph
+    // bytes. Without them the SCHIP big glyphs are used, so Fx30 still has something to point at
+    pub fn from_bytes(data: &[u8]) -> Option<Self> {
+        match data.len() {
+            SMALL_FONT_SIZE => Some(Self {
+                small: data.to_vec(),
+                big: SCHIP_BIG_FONT.to_vec(),
+            }),
+            len if len == SMALL_FONT_SIZE + BIG_FONT_SIZE => Some(Self {
+                small: data[..SMALL_FONT_SIZE].to_vec(),
+                big: data[SMALL_FONT_SIZE..].to_vec(),
+            }),
+            _ => None,
+        }
+    }
+
+    // Offset of the big glyphs from the font address
+    pub fn big_offset(&self) -> u16 {
+        SMALL_FONT_SIZE as u16
+    }
+
+    pub fn len(&self) -> usize {
+        self.small.len() + self.big.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    // The glyph bytes as they are laid out in memory
+    pub fn bytes(&self) -> Vec<u8> {
+        let mut bytes = self.small.clone();
+        bytes.extend(&self.big);
+
+        bytes
+    }
+}
@@ -0,0 +1,42 @@
+// The MIT License (MIT)
+
+// Copyright (c) 2021 AnonymousDapper
+
+// Host key for each CHIP-8 key 0x0 to 0xF, laid out like the VIP hex keypad
+const DEFAULT_KEYS: [char; 16] = [
+    'x', '1', '2', '3', 'q', 'w', 'e', 'a', 's', 'd', 'z', 'c', '4', 'r', 'f', 'v',
+];
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Keymap {
+    extra: Vec<(char, u8)>, // Checked before the default layout
+}
+
+impl Keymap {
+    pub fn new(extra: Vec<(char, u8)>) -> Self {
+        Self { extra }
+    }
+
+    pub fn translate(&self, key: char) -> Option<u8> {
+        let key = key.to_ascii_lowercase();
+
+        self.extra
+            .iter()
+            .find(|(host, _)| *host == key)
+            .map(|(_, code)| *code)
+            .or_else(|| DEFAULT_KEYS.iter().position(|&c| c == key).map(|i| i as u8))
+    }
+
+    // Every host key that maps to a CHIP-8 key
+    pub fn entries(&self) -> Vec<(char, u8)> {
+        let mut entries = self.extra.clone();
+
+        for (code, &key) in DEFAULT_KEYS.iter().enumerate() {
+            if !entries.iter().any(|(host, _)| *host == key) {
+                entries.push((key, code as u8));
+            }
+        }
+
+        entries
+    }
+}
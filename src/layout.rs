@@ -0,0 +1,230 @@
+// The MIT License (MIT)
+
+// Copyright (c) 2021 AnonymousDapper
+
+use super::display::{Renderer, DISPLAY_HEIGHT, DISPLAY_WIDTH};
+
+// Fallback used when the terminal size can't be queried (e.g. output is piped)
+const DEFAULT_TERM_SIZE: (usize, usize) = (80, 24);
+
+const PANE_GAP: usize = 4;
+
+pub const REGISTERS_WIDTH: usize = 14;
+pub const REGISTERS_HEIGHT: usize = 24;
+pub const REGISTERS_COMPACT_WIDTH: usize = 24;
+pub const REGISTERS_COMPACT_HEIGHT: usize = 13;
+pub const STACK_WIDTH: usize = 12;
+pub const DIS_MIN_WIDTH: usize = 24;
+pub const DIS_MAX_WIDTH: usize = 56;
+pub const DIS_MAX_LINES: usize = 32;
+pub const MEMORY_WIDTH: usize = 31;
+pub const MEMORY_MAX_LINES: usize = 16;
+pub const SPRITE_WIDTH: usize = 22;
+pub const SPRITE_MAX_LINES: usize = 16;
+
+// All positions are 1-based terminal coordinates
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Rect {
+    pub row: usize,
+    pub col: usize,
+    pub width: usize,
+    pub height: usize,
+}
+
+impl Rect {
+    pub fn bottom(&self) -> usize {
+        self.row + self.height
+    }
+
+    pub fn right(&self) -> usize {
+        self.col + self.width
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Layout {
+    pub term_size: (usize, usize),
+    pub title_row: usize,
+    pub frame: Rect, // Display border
+    pub display: Rect,
+    pub registers: Option<Rect>,
+    pub stack: Option<Rect>,
+    pub disassembly: Option<Rect>,
+    pub memory: Option<Rect>,
+    pub sprite: Option<Rect>,
+    pub status_row: usize,
+}
+
+pub fn terminal_size() -> (usize, usize) {
+    match termion::terminal_size() {
+        Ok((cols, rows)) if cols > 0 && rows > 0 => (cols as usize, rows as usize),
+        _ => DEFAULT_TERM_SIZE,
+    }
+}
+
+impl Layout {
+    pub fn new(renderer: Renderer, debug: bool) -> Self {
+        Self::compute(terminal_size(), renderer, debug)
+    }
+
+    pub fn compute(term_size: (usize, usize), renderer: Renderer, debug: bool) -> Self {
+        let (term_cols, term_rows) = term_size;
+        let (columns, rows) = renderer.size(DISPLAY_WIDTH, DISPLAY_HEIGHT);
+        let side = renderer.side_width();
+
+        let frame_width = columns + side * 2;
+        let frame_height = rows + 2;
+
+        // Keep the original margins when there's room for them
+        let left = if term_cols >= frame_width + 7 { 8 } else { 1 };
+        let title_row = if term_rows >= frame_height + 4 { 3 } else { 1 };
+
+        let frame = Rect {
+            row: title_row + 1,
+            col: left,
+            width: frame_width,
+            height: frame_height,
+        };
+
+        let display = Rect {
+            row: frame.row + 1,
+            col: frame.col + side,
+            width: columns,
+            height: rows,
+        };
+
+        let mut layout = Self {
+            term_size,
+            title_row,
+            frame,
+            display,
+            registers: None,
+            stack: None,
+            disassembly: None,
+            memory: None,
+            sprite: None,
+            status_row: frame.bottom(),
+        };
+
+        if debug {
+            // Panes go to the right of the display, unless more of them fit below it
+            let mut right = layout.clone();
+            let mut below = layout.clone();
+
+            let right_count = right.place_panes(frame.row, frame.right() + PANE_GAP);
+            let below_count = below.place_panes(frame.bottom() + 1, 1);
+
+            layout = if below_count > right_count {
+                below
+            } else {
+                right
+            };
+        }
+
+        layout.status_row = layout.status_row.min(term_rows);
+
+        layout
+    }
+
+    fn place_panes(&mut self, row: usize, col: usize) -> usize {
+        let (term_cols, term_rows) = self.term_size;
+        let avail_rows = (term_rows + 1).saturating_sub(row);
+        let mut col = col;
+
+        if col + REGISTERS_WIDTH <= term_cols + 1 && avail_rows >= REGISTERS_HEIGHT {
+            self.registers = Some(Rect {
+                row,
+                col,
+                width: REGISTERS_WIDTH,
+                height: REGISTERS_HEIGHT,
+            });
+            col += REGISTERS_WIDTH + PANE_GAP;
+        } else if col + REGISTERS_COMPACT_WIDTH <= term_cols + 1
+            && avail_rows >= REGISTERS_COMPACT_HEIGHT
+        {
+            // V registers are split over two columns
+            self.registers = Some(Rect {
+                row,
+                col,
+                width: REGISTERS_COMPACT_WIDTH,
+                height: REGISTERS_COMPACT_HEIGHT,
+            });
+            col += REGISTERS_COMPACT_WIDTH + PANE_GAP;
+        }
+
+        // Header, gap, and at least a couple of entries
+        if col + STACK_WIDTH <= term_cols + 1 && avail_rows >= 4 {
+            self.stack = Some(Rect {
+                row,
+                col,
+                width: STACK_WIDTH,
+                height: avail_rows.min(REGISTERS_HEIGHT),
+            });
+            col += STACK_WIDTH + PANE_GAP;
+        }
+
+        if col + DIS_MIN_WIDTH <= term_cols + 1 && avail_rows >= 5 {
+            // Narrowed to leave room for the memory pane beside it, if that's enough to fit it
+            let mut avail_cols = term_cols + 1 - col;
+            if avail_cols >= DIS_MIN_WIDTH + PANE_GAP + MEMORY_WIDTH {
+                avail_cols -= PANE_GAP + MEMORY_WIDTH;
+            }
+
+            let width = avail_cols.min(DIS_MAX_WIDTH);
+
+            self.disassembly = Some(Rect {
+                row,
+                col,
+                width,
+                height: avail_rows.min(DIS_MAX_LINES + 2),
+            });
+            col += width + PANE_GAP;
+        }
+
+        // Header, address line and at least a few rows of bytes
+        if col + MEMORY_WIDTH <= term_cols + 1 && avail_rows >= 6 {
+            self.memory = Some(Rect {
+                row,
+                col,
+                width: MEMORY_WIDTH,
+                height: avail_rows.min(MEMORY_MAX_LINES + 2),
+            });
+            col += MEMORY_WIDTH + PANE_GAP;
+        }
+
+        // Goes under the memory pane when there's room, otherwise beside it. Either way it needs
+        // the header, status line and 8 rows of pixels
+        let (sprite_row, sprite_col) = match self.memory {
+            Some(memory) if term_rows + 1 >= memory.bottom() + 1 + 10 => {
+                (memory.bottom() + 1, memory.col)
+            }
+            _ => (row, col),
+        };
+        let sprite_rows = (term_rows + 1).saturating_sub(sprite_row);
+
+        if sprite_col + SPRITE_WIDTH <= term_cols + 1 && sprite_rows >= 10 {
+            self.sprite = Some(Rect {
+                row: sprite_row,
+                col: sprite_col,
+                width: SPRITE_WIDTH,
+                height: sprite_rows.min(SPRITE_MAX_LINES + 2),
+            });
+        }
+
+        let panes = [
+            self.registers,
+            self.stack,
+            self.disassembly,
+            self.memory,
+            self.sprite,
+        ];
+        let mut placed = 0;
+
+        for pane in panes.iter().flatten() {
+            self.status_row = self.status_row.max(pane.bottom());
+            placed += 1;
+        }
+
+        placed
+    }
+}
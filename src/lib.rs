@@ -3,119 +3,139 @@
 // Copyright (c) 2021 AnonymousDapper
 
 #![deny(rust_2018_idioms)]
-#![feature(array_map)]
 
+pub mod compare;
+pub mod coverage;
+#[cfg(feature = "terminal")]
 pub mod dis;
+#[cfg(feature = "terminal")]
 pub mod display;
-
-use std::convert::TryInto;
-use std::io::{stdin, stdout, Write};
-use std::thread;
-use std::time::{Duration, Instant};
-
-use termion::event::Key;
-use termion::input::TermRead;
-use termion::raw::IntoRawMode;
-
-#[rustfmt::skip]
-static FONT: [u8; 80] = [
-    0xF0, 0x90, 0x90, 0x90, 0xF0,
-    0x20, 0x60, 0x20, 0x20, 0x70,
-    0xF0, 0x10, 0xF0, 0x80, 0xF0,
-    0xF0, 0x10, 0xF0, 0x10, 0xF0,
-    0x90, 0x90, 0xF0, 0x10, 0x10,
-    0xF0, 0x80, 0xF0, 0x10, 0xF0,
-    0xF0, 0x80, 0xF0, 0x90, 0xF0,
-    0xF0, 0x10, 0x20, 0x40, 0x40,
-    0xF0, 0x90, 0xF0, 0x90, 0xF0,
-    0xF0, 0x90, 0xF0, 0x10, 0xF0,
-    0xF0, 0x90, 0xF0, 0x90, 0x90,
-    0xE0, 0x90, 0xE0, 0x90, 0xE0,
-    0xF0, 0x80, 0x80, 0x80, 0xF0,
-    0xE0, 0x90, 0x90, 0x90, 0xE0,
-    0xF0, 0x80, 0xF0, 0x80, 0xF0,
-    0xF0, 0x80, 0xF0, 0x80, 0x80,
-];
-
-pub fn handle_ctrlc() {
-    print!("\x1b[45;0H{}", termion::cursor::Show);
-    std::process::exit(1);
+pub mod env;
+pub mod flow;
+pub mod font;
+pub mod keymap;
+#[cfg(feature = "terminal")]
+pub mod layout;
+pub mod memmap;
+#[cfg(feature = "terminal")]
+pub mod memview;
+pub mod palette;
+pub mod platform;
+pub mod profile;
+pub mod rom;
+#[cfg(feature = "terminal")]
+pub mod rpc;
+pub mod script;
+pub mod smc;
+// Host clock speed sampling, std's clock panics on wasm32-unknown-unknown
+#[cfg(any(feature = "terminal", feature = "window"))]
+pub mod speed;
+#[cfg(feature = "terminal")]
+pub mod spriteview;
+pub mod symbols;
+#[cfg(feature = "terminal")]
+pub mod term;
+pub mod timing;
+pub mod vp590;
+#[cfg(feature = "wasm")]
+pub mod wasm;
+#[cfg(feature = "window")]
+pub mod window;
+
+use std::collections::{BTreeSet, VecDeque};
+use std::fmt;
+
+use coverage::Coverage;
+use font::{Font, BIG_GLYPH_SIZE, SMALL_GLYPH_SIZE};
+use memmap::{Access, MemoryEvent, MemoryMap, Policy};
+use platform::{Platform, Quirks};
+use profile::Profiler;
+use smc::SmcDetector;
+use symbols::Symbols;
+use timing::Timing;
+use vp590::ColourMap;
+
+pub const DISPLAY_HEIGHT: usize = 32;
+pub const DISPLAY_WIDTH: usize = 64;
+
+// Instructions run per 60Hz frame
+pub const DEFAULT_IPF: usize = 10;
+pub const MAX_IPF: usize = 10000;
+
+// Frontends call `System::run_frame` at this rate, which also ticks the timers
+pub const TIMER_HZ: u32 = 60;
+
+// The VIP interpreter reserves 0xEA0-0xECF for the stack, which grows down from the top
+pub const STACK_AREA_SIZE: usize = 0x30;
+
+// Memory events kept for the frontend before the oldest are dropped
+const MAX_MEMORY_EVENTS: usize = 256;
+
+// Faults caused by the program being run rather than the emulator, these halt the system
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum GuestError {
+    StackOverflow {
+        pc: u16,
+        depth: usize,
+    },
+    StackUnderflow {
+        pc: u16,
+    },
+    AccessFault {
+        access: Access,
+        addr: u16,
+        pc: u16,
+        region: &'static str,
+    },
+    IllegalOpcode {
+        pc: u16,
+        op: u16,
+    },
 }
 
-fn translate_keypad(key: char) -> Option<u8> {
-    match key {
-        '1' => Some(1),
-        '2' => Some(2),
-        '3' => Some(3),
-        '4' => Some(0xc),
-        'q' => Some(4),
-        'w' => Some(5),
-        'e' => Some(6),
-        'r' => Some(0xd),
-        'a' => Some(7),
-        's' => Some(8),
-        'd' => Some(9),
-        'f' => Some(0xe),
-        'z' => Some(0xa),
-        'x' => Some(0),
-        'c' => Some(0xb),
-        'v' => Some(0xf),
-        _ => None,
-    }
-}
-
-fn scan_key(code: u8) -> bool {
-    let mut stdout = stdout().into_raw_mode().unwrap();
-    let stdin = stdin();
-    let mut rv: bool = false;
-
-    for c in stdin.keys() {
-        if let Key::Ctrl('c') = c.as_ref().unwrap() {
-            handle_ctrlc();
-        }
-
-        if let Key::Char(key) = c.unwrap() {
-            if let Some(key_code) = translate_keypad(key) {
-                if key_code == code {
-                    rv = true;
-                }
+impl fmt::Display for GuestError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::StackOverflow { pc, depth } => write!(
+                f,
+                "stack overflow at {:#06x}, more than {} nested calls",
+                pc, depth
+            ),
+            Self::AccessFault {
+                access,
+                addr,
+                pc,
+                region,
+            } => write!(
+                f,
+                "{} {} at {:#06x} from {:#06x} is not allowed",
+                access, region, addr, pc
+            ),
+            Self::StackUnderflow { pc } => {
+                write!(f, "stack underflow at {:#06x}, RET outside a call", pc)
             }
+            Self::IllegalOpcode { pc, op } => write!(
+                f,
+                "illegal opcode {:#06x} at {:#06x} for this platform",
+                op, pc
+            ),
         }
-        stdout.flush().unwrap();
     }
-
-    std::mem::drop(stdout);
-    rv
 }
 
-fn wait_key() -> u8 {
-    let mut stdout = stdout().into_raw_mode().unwrap();
-    let mut got_key = None;
-
-    while got_key.is_none() {
-        let stdin = stdin();
-        for c in stdin.keys() {
-            if let Key::Ctrl('c') = c.as_ref().unwrap() {
-                handle_ctrlc();
-            }
-
-            if let Key::Char(key) = c.unwrap() {
-                if let Some(key_code) = translate_keypad(key) {
-                    got_key = Some(key_code);
-                    break;
-                }
-            }
-            stdout.flush().unwrap();
+impl GuestError {
+    pub fn pc(&self) -> u16 {
+        match *self {
+            Self::StackOverflow { pc, .. } => pc,
+            Self::StackUnderflow { pc } => pc,
+            Self::AccessFault { pc, .. } => pc,
+            Self::IllegalOpcode { pc, .. } => pc,
         }
-
-        thread::sleep(Duration::from_millis(5));
     }
-
-    std::mem::drop(stdout);
-
-    got_key.unwrap()
 }
 
+impl std::error::Error for GuestError {}
+
 #[derive(Debug)]
 pub struct InstHelper {
     pub nnn: u16,
@@ -124,148 +144,215 @@ pub struct InstHelper {
     pub y: usize,
 }
 
-#[derive(Debug)]
+// Counts down once per call to `tick`, which happens once per 60Hz frame
+#[derive(Debug, Clone)]
 pub struct Timer60Hz {
     val: u8,
-    t: Option<Instant>,
-    time_scale: u64,
 }
 
 impl Timer60Hz {
     #[allow(clippy::new_without_default)]
     pub fn new() -> Self {
-        Self {
-            val: 0,
-            t: None,
-            time_scale: 1,
-        }
-    }
-
-    pub fn with_time_scale(scale: u64) -> Self {
-        Self {
-            val: 0,
-            t: None,
-            time_scale: scale,
-        }
-    }
-
-    pub fn set_time_scale(&mut self, scale: u64) {
-        self.time_scale = scale;
+        Self { val: 0 }
     }
 
     pub fn set(&mut self, v: u8) {
-        self.t = Some(Instant::now());
         self.val = v;
     }
 
-    pub fn get(&mut self) -> u8 {
-        if self.t.is_none() {
-            0
-        } else {
-            let now = Instant::now();
-            let elapsed = now.duration_since(self.t.unwrap()).as_millis();
-            let elapsed_ticks = elapsed / 16 / self.time_scale as u128;
-            if elapsed_ticks > self.val.into() {
-                self.val = 0;
-                self.t = None;
-                0
-            } else {
-                (self.val as u128 - elapsed_ticks).try_into().unwrap()
-            }
-        }
+    pub fn get(&self) -> u8 {
+        self.val
     }
 
-    pub fn get_no_mod(&self) -> u8 {
-        if self.t.is_none() {
-            0
-        } else {
-            let now = Instant::now();
-            let elapsed = now.duration_since(self.t.unwrap()).as_millis();
-            let elapsed_ticks = elapsed / 16;
-            if elapsed_ticks > self.val.into() {
-                0
-            } else {
-                (self.val as u128 - elapsed_ticks).try_into().unwrap()
-            }
-        }
+    pub fn tick(&mut self) {
+        self.val = self.val.saturating_sub(1);
     }
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct Memory {
-    pub ram: [u8; 4096],
-    pub stack: Vec<u16>,
-    pub scratch: [u8; 32],
-    pub display: [u8; 2048],
+    ram: Vec<u8>,
+    stack: Vec<u16>,
+    stack_depth: usize,
+    stack_base: Option<u16>, // Entries are mirrored into RAM from here when set
+    display: [u8; 2048],
+    map: MemoryMap,
+    font: Font,
+    font_address: u16,
 }
 
 impl Memory {
     #[allow(clippy::new_without_default)]
     pub fn new() -> Self {
+        Self::for_platform(Platform::default())
+    }
+
+    pub fn for_platform(platform: Platform) -> Self {
         let mut tmp = Self {
-            ram: [0; 4096],
+            ram: vec![0; platform.memory_size()],
             stack: Vec::new(),
-            scratch: [0; 32],
+            stack_depth: platform.stack_depth(),
+            stack_base: platform.stack_base(),
             display: [0; 2048],
+            map: MemoryMap::for_platform(platform),
+            font: platform.font_set().font(),
+            font_address: platform.font_address(),
         };
 
-        // Nasty
-        for (i, x) in FONT.iter().enumerate() {
-            tmp.ram[i] = *x;
-        }
+        tmp.clear();
 
         tmp
     }
 
-    pub fn load(&mut self, buf: &[u8]) {
-        for (i, c) in buf.iter().enumerate() {
-            self.ram[0x200 + i] = *c;
+    pub fn ram(&self) -> &[u8] {
+        &self.ram
+    }
+
+    pub fn stack(&self) -> &[u16] {
+        &self.stack
+    }
+
+    pub fn map(&self) -> &MemoryMap {
+        &self.map
+    }
+
+    pub fn map_mut(&mut self) -> &mut MemoryMap {
+        &mut self.map
+    }
+
+    pub fn font(&self) -> &Font {
+        &self.font
+    }
+
+    pub fn font_address(&self) -> u16 {
+        self.font_address
+    }
+
+    // Returns false if the font wouldn't fit below `limit`, takes effect on the next `reload`
+    pub fn set_font(&mut self, font: Font, address: u16, limit: u16) -> bool {
+        let end = address as usize + font.len();
+
+        if end > limit as usize {
+            return false;
         }
+
+        self.map.set_font_area(address as usize, end);
+        self.font = font;
+        self.font_address = address;
+
+        true
+    }
+
+    pub fn load(&mut self, buf: &[u8], address: u16) {
+        let start = address as usize;
+        self.ram[start..start + buf.len()].copy_from_slice(buf);
     }
 
-    pub fn reload(&mut self, buf: &[u8]) {
+    pub fn reload(&mut self, buf: &[u8], address: u16) {
         self.clear();
-        self.load(buf);
+        self.load(buf, address);
     }
 
+    // Back to power-on state, with only the font left in RAM
     fn clear(&mut self) {
-        self.ram.map(|_| 0);
-        self.scratch.map(|_| 0);
-        self.display.map(|_| 0);
+        let font = self.font_address as usize;
+        let bytes = self.font.bytes();
+
+        self.ram.fill(0);
+        self.ram[font..font + bytes.len()].copy_from_slice(&bytes);
+        self.clear_display();
         self.stack.clear();
     }
 
+    pub fn stack_depth(&self) -> usize {
+        self.stack_depth
+    }
+
+    // A stack kept in RAM can't grow past its reserved area
+    pub fn set_stack_depth(&mut self, depth: usize) {
+        self.stack_depth = match self.stack_base {
+            Some(_) => depth.clamp(1, STACK_AREA_SIZE / 2),
+            None => depth.max(1),
+        };
+        self.stack.truncate(self.stack_depth);
+    }
+
+    pub fn stack_base(&self) -> Option<u16> {
+        self.stack_base
+    }
+
+    // Returns false when the stack is already full
+    pub fn push_stack(&mut self, addr: u16) -> bool {
+        if self.stack.len() >= self.stack_depth {
+            return false;
+        }
+
+        if let Some(slot) = self.stack_slot(self.stack.len()) {
+            self.write_u8(slot, (addr >> 8) as u8);
+            self.write_u8(slot + 1, addr as u8);
+        }
+
+        self.stack.push(addr);
+
+        true
+    }
+
+    // A stack in RAM is read back from there, so programs that poke at it see the effect
+    pub fn pop_stack(&mut self) -> Option<u16> {
+        let addr = self.stack.pop()?;
+
+        match self.stack_slot(self.stack.len()) {
+            Some(slot) => Some(self.read_u16(slot)),
+            None => Some(addr),
+        }
+    }
+
+    fn stack_slot(&self, level: usize) -> Option<u16> {
+        let top = self.stack_base? + STACK_AREA_SIZE as u16;
+
+        Some(top - 2 * (level as u16 + 1))
+    }
+
+    // Addresses wrap around the end of RAM
     pub fn read_u16(&self, ip: u16) -> u16 {
-        (self.ram[ip as usize] as u16) << 8 | (self.ram[ip as usize + 1] as u16)
+        (self.read_u8(ip) as u16) << 8 | (self.read_u8(ip.wrapping_add(1)) as u16)
     }
 
     pub fn read_u8(&self, ip: u16) -> u8 {
-        self.ram[ip as usize]
+        self.ram[ip as usize % self.ram.len()]
     }
 
     pub fn write_u8(&mut self, ip: u16, v: u8) {
-        self.ram[ip as usize] = v;
+        let len = self.ram.len();
+        self.ram[ip as usize % len] = v;
     }
 
     pub fn clear_display(&mut self) {
-        self.display.map(|_| 0);
+        self.display.fill(0);
     }
 
-    pub fn write_sprite(&mut self, x: u8, y: u8, data: &[u8]) -> bool {
-        use display::DISPLAY_WIDTH;
-
+    // With `clip` unset, sprites wrap around to the opposite edge instead
+    pub fn write_sprite(&mut self, x: u8, y: u8, data: &[u8], clip: bool) -> bool {
         let mut unset = false;
 
-        let rx = x % 64;
-        let ry = y % 32;
+        let rx = x as usize % DISPLAY_WIDTH;
+        let ry = y as usize % DISPLAY_HEIGHT;
 
         for (yi, chunk) in data.iter().enumerate() {
-            let ryi = ry as usize + yi;
-            if ryi < 32 {
+            let mut ryi = ry + yi;
+            if !clip {
+                ryi %= DISPLAY_HEIGHT;
+            }
+
+            if ryi < DISPLAY_HEIGHT {
                 let p_data = chunk.reverse_bits();
                 for xi in 0..8 {
-                    let rxi = (rx + xi) as usize;
-                    if rxi < 64 {
+                    let mut rxi = rx + xi;
+                    if !clip {
+                        rxi %= DISPLAY_WIDTH;
+                    }
+
+                    if rxi < DISPLAY_WIDTH {
                         let pixel = p_data >> xi & 0x1;
 
                         if pixel & self.display[rxi + ryi * DISPLAY_WIDTH] == 0x1 {
@@ -282,32 +369,79 @@ impl Memory {
     }
 }
 
-#[derive(Debug)]
+// Cloning takes a snapshot of the whole machine
+#[derive(Debug, Clone)]
 pub struct System {
     mem: Memory,
     pc: u16, // Program Counter
     ir: u16, // Index Register
     //dt: u8,  // Delay Timer // TODO: maybe Instant computation for dt instead
     dt: Timer60Hz,
-    st: u8, // Sound Timer
+    st: Timer60Hz, // Sound Timer
     registers: [u8; 16],
-    cycle_delay_ms: Duration,
+    ipf: usize, // Instructions per frame
+    timing: Timing,
+    cycle_budget: i64, // VIP machine cycles left this frame, negative when overdrawn
+    instructions: u64, // Executed since power on
+    frames: u64,
     halted: bool,
+    error: Option<GuestError>,
+    breakpoints: BTreeSet<u16>,
+    break_hit: Option<u16>, // Set while stopped on a breakpoint, before the instruction runs
+    resuming: bool,         // Lets the instruction under a breakpoint run once
+    delay_wait: bool,       // Set while CHIP-8E's Fx4F waits for DT to run out
+    colour_map: Option<ColourMap>, // CHIP-8X only
+    memory_events: VecDeque<MemoryEvent>,
+    keys: [bool; 16],
+    wait_register: Option<usize>, // Set while Fx0A is waiting for a key press
+    redraw: bool,
+    rom: Vec<u8>,
+    symbols: Symbols,
+    coverage: Option<Coverage>,
+    profiler: Option<Profiler>,
+    smc: Option<SmcDetector>,
+    platform: Platform,
+    load_address: u16,
+    entry_address: u16,
+    quirks: Quirks,
 }
 
 impl System {
     #[allow(clippy::new_without_default)]
-    pub fn new(delay: u64) -> Self {
+    pub fn new(ipf: usize) -> Self {
         Self {
             mem: Memory::new(),
-            pc: 0x200,
+            pc: Platform::default().entry_address(),
             ir: 0,
             //dt: 0,
-            dt: Timer60Hz::with_time_scale(delay / 2),
-            st: 0,
+            dt: Timer60Hz::new(),
+            st: Timer60Hz::new(),
             registers: [0; 16],
-            cycle_delay_ms: Duration::from_millis(delay),
+            ipf: ipf.clamp(1, MAX_IPF),
+            timing: Timing::default(),
+            cycle_budget: 0,
+            instructions: 0,
+            frames: 0,
             halted: false,
+            error: None,
+            breakpoints: BTreeSet::new(),
+            break_hit: None,
+            resuming: false,
+            delay_wait: false,
+            colour_map: None,
+            memory_events: VecDeque::new(),
+            keys: [false; 16],
+            wait_register: None,
+            redraw: false,
+            rom: Vec::new(),
+            symbols: Symbols::new(),
+            coverage: None,
+            profiler: None,
+            smc: None,
+            platform: Platform::default(),
+            load_address: Platform::default().load_address(),
+            entry_address: Platform::default().entry_address(),
+            quirks: Platform::default().quirks(),
         }
     }
 
@@ -328,24 +462,340 @@ impl System {
     }
 
     fn reset(&mut self) {
-        self.pc = 0x200;
+        self.pc = self.entry_address;
         self.ir = 0;
-        self.dt = Timer60Hz::new();
-        self.st = 0;
-        self.registers.map(|_| 0);
+        self.dt.set(0);
+        self.st.set(0);
+        self.registers.fill(0);
+        self.halted = false;
+        self.error = None;
+        self.break_hit = None;
+        self.resuming = false;
+        self.wait_register = None;
+        self.delay_wait = false;
+        self.cycle_budget = 0;
+        self.colour_map = match self.platform {
+            Platform::Chip8X => Some(ColourMap::new()),
+            _ => None,
+        };
+    }
+
+    pub fn ipf(&self) -> usize {
+        self.ipf
+    }
+
+    pub fn set_ipf(&mut self, ipf: usize) {
+        self.ipf = ipf.clamp(1, MAX_IPF);
+    }
+
+    pub fn timing(&self) -> Timing {
+        self.timing
+    }
+
+    pub fn set_timing(&mut self, timing: Timing) {
+        self.timing = timing;
+        self.cycle_budget = 0;
+    }
+
+    pub fn instructions(&self) -> u64 {
+        self.instructions
+    }
+
+    pub fn frames(&self) -> u64 {
+        self.frames
+    }
+
+    pub fn platform(&self) -> Platform {
+        self.platform
+    }
+
+    // Also resets the quirks, addresses and memory layout to the platform defaults, takes effect on
+    // the next `load_rom`
+    pub fn set_platform(&mut self, platform: Platform) {
+        self.platform = platform;
+        self.quirks = platform.quirks();
+        self.load_address = platform.load_address();
+        self.entry_address = platform.entry_address();
+        self.mem = Memory::for_platform(platform);
+
+        // Memory may have changed size
+        if self.coverage.is_some() {
+            self.enable_coverage();
+        }
+        if self.profiler.is_some() {
+            self.enable_profiling();
+        }
+        if self.smc.is_some() {
+            self.enable_smc_detection();
+        }
+    }
+
+    pub fn load_address(&self) -> u16 {
+        self.load_address
+    }
+
+    // Moving the load address also moves the entry point with it
+    pub fn set_load_address(&mut self, address: u16) {
+        self.load_address = address;
+        self.entry_address = address;
+        self.mem.map_mut().set_program_start(address as usize);
+    }
+
+    pub fn entry_address(&self) -> u16 {
+        self.entry_address
+    }
+
+    pub fn set_entry_address(&mut self, address: u16) {
+        self.entry_address = address;
+    }
+
+    pub fn set_stack_depth(&mut self, depth: usize) {
+        self.mem.set_stack_depth(depth);
+    }
+
+    // The font has to sit below the program, returns false if it doesn't fit
+    pub fn set_font(&mut self, font: Font, address: u16) -> bool {
+        self.mem.set_font(font, address, self.load_address)
+    }
+
+    pub fn quirks(&self) -> Quirks {
+        self.quirks
+    }
+
+    pub fn set_quirks(&mut self, quirks: Quirks) {
+        self.quirks = quirks;
+    }
+
+    pub fn set_key(&mut self, key: u8, pressed: bool) {
+        let key = (key & 0xF) as usize;
+
+        if pressed && !self.keys[key] {
+            if let Some(x) = self.wait_register.take() {
+                self.registers[x] = key as u8;
+            }
+        }
+
+        self.keys[key] = pressed;
+    }
+
+    pub fn memory(&self) -> &Memory {
+        &self.mem
+    }
+
+    pub fn pc(&self) -> u16 {
+        self.pc
+    }
+
+    pub fn ir(&self) -> u16 {
+        self.ir
+    }
+
+    pub fn dt(&self) -> u8 {
+        self.dt.get()
+    }
+
+    pub fn st(&self) -> u8 {
+        self.st.get()
+    }
+
+    pub fn display(&self) -> &[u8] {
+        &self.mem.display
+    }
+
+    // Foreground and background colours on CHIP-8X
+    pub fn colour_map(&self) -> Option<&ColourMap> {
+        self.colour_map.as_ref()
+    }
+
+    // Returns true once after each change to the display
+    pub fn take_redraw(&mut self) -> bool {
+        std::mem::replace(&mut self.redraw, false)
     }
 
     pub fn halt(&mut self) {
         self.halted = true;
     }
 
-    pub fn load_rom(&mut self, data: &[u8]) {
-        self.mem.reload(data);
+    pub fn is_halted(&self) -> bool {
+        self.halted
+    }
+
+    pub fn error(&self) -> Option<GuestError> {
+        self.error
+    }
+
+    pub fn symbols(&self) -> &Symbols {
+        &self.symbols
+    }
+
+    pub fn set_symbols(&mut self, symbols: Symbols) {
+        self.symbols = symbols;
+    }
+
+    pub fn coverage(&self) -> Option<&Coverage> {
+        self.coverage.as_ref()
+    }
+
+    // Drops any coverage gathered so far. Restarts keep it, so several runs of a ROM add up
+    pub fn enable_coverage(&mut self) {
+        self.coverage = Some(Coverage::new(self.mem.ram.len()));
+    }
+
+    pub fn profiler(&self) -> Option<&Profiler> {
+        self.profiler.as_ref()
+    }
+
+    // Counts start again at zero, with the call tree rooted at the ROM's entry point
+    pub fn enable_profiling(&mut self) {
+        self.profiler = Some(Profiler::new(self.mem.ram.len(), self.entry_address));
+    }
+
+    pub fn smc(&self) -> Option<&SmcDetector> {
+        self.smc.as_ref()
+    }
+
+    // Only writes made from here on are checked, code patched before now isn't reported
+    pub fn enable_smc_detection(&mut self) {
+        self.smc = Some(SmcDetector::new(self.mem.ram.len()));
+    }
+
+    pub fn breakpoints(&self) -> &BTreeSet<u16> {
+        &self.breakpoints
+    }
+
+    pub fn set_breakpoint(&mut self, addr: u16, enabled: bool) {
+        if enabled {
+            self.breakpoints.insert(addr);
+        } else {
+            self.breakpoints.remove(&addr);
+        }
+    }
+
+    pub fn toggle_breakpoint(&mut self, addr: u16) {
+        self.set_breakpoint(addr, !self.breakpoints.contains(&addr));
+    }
+
+    // The breakpoint execution is stopped on
+    pub fn break_hit(&self) -> Option<u16> {
+        self.break_hit
+    }
+
+    // Carries on from a breakpoint, starting with the instruction under it
+    pub fn resume(&mut self) {
+        if self.break_hit.take().is_some() {
+            self.resuming = true;
+        }
+    }
+
+    fn fault(&mut self, error: GuestError) {
+        self.error = Some(error);
+        self.halted = true;
+    }
+
+    pub fn memory_map_mut(&mut self) -> &mut MemoryMap {
+        self.mem.map_mut()
+    }
+
+    // Drains the writes and jumps that were logged, warned about or faulted on since the last call
+    pub fn take_memory_events(&mut self) -> Vec<MemoryEvent> {
+        self.memory_events.drain(..).collect()
+    }
+
+    // Applies the memory map policy for `addr`, returns false if the access faulted
+    fn check_access(&mut self, addr: u16, access: Access, pc: u16) -> bool {
+        let region = match self.mem.map.region(addr) {
+            Some(region) if region.policy(access) != Policy::Allow => *region,
+            _ => return true,
+        };
+
+        if self.memory_events.len() >= MAX_MEMORY_EVENTS {
+            self.memory_events.pop_front();
+        }
+
+        self.memory_events.push_back(MemoryEvent {
+            access,
+            addr,
+            pc,
+            region: region.name,
+            policy: region.policy(access),
+        });
+
+        if region.policy(access) == Policy::Fault {
+            self.fault(GuestError::AccessFault {
+                access,
+                addr,
+                pc,
+                region: region.name,
+            });
+
+            return false;
+        }
+
+        true
+    }
+
+    // Writes made by the program, as opposed to the interpreter's own stack
+    fn write_mem(&mut self, addr: u16, value: u8) {
+        let addr = (addr as usize % self.mem.ram.len()) as u16;
+
+        let pc = self.pc.wrapping_sub(2);
+
+        if self.check_access(addr, Access::Write, pc) {
+            self.mem.write_u8(addr, value);
+
+            if let Some(ref mut coverage) = self.coverage {
+                coverage.mark_written(addr);
+            }
+            if let Some(ref mut smc) = self.smc {
+                smc.mark_written(addr, pc);
+            }
+        }
+    }
+
+    // Reads program data, as opposed to fetching instructions
+    fn read_mem(&mut self, addr: u16) -> u8 {
+        if let Some(ref mut coverage) = self.coverage {
+            coverage.mark_read(addr);
+        }
+
+        self.mem.read_u8(addr)
+    }
+
+    pub fn is_waiting(&self) -> bool {
+        self.wait_register.is_some() || (self.delay_wait && self.dt.get() > 0)
+    }
+
+    fn is_running(&self) -> bool {
+        !self.halted && self.break_hit.is_none() && !self.is_waiting()
+    }
+
+    pub fn load_rom(&mut self, data: &[u8]) -> Result<(), rom::RomError> {
+        rom::validate(
+            data,
+            self.mem
+                .ram
+                .len()
+                .saturating_sub(self.load_address as usize),
+        )?;
+
+        self.rom = data.to_vec();
+        self.restart();
+
+        Ok(())
+    }
+
+    pub fn restart(&mut self) {
+        self.mem.reload(&self.rom, self.load_address);
         self.reset();
+        self.redraw = true;
     }
 
     pub fn read_decode(&self) -> (u16, (u8, u8, u8, u8), InstHelper) {
-        let instruction: u16 = self.mem.read_u16(self.pc);
+        self.decode_at(self.pc)
+    }
+
+    pub fn decode_at(&self, addr: u16) -> (u16, (u8, u8, u8, u8), InstHelper) {
+        let instruction: u16 = self.mem.read_u16(addr);
 
         let decoded = (
             ((instruction & 0xF000) >> 12) as u8,
@@ -364,57 +814,240 @@ impl System {
         (instruction, decoded, helper)
     }
 
-    pub fn run(&mut self, mut maybe_dis: Option<&mut dis::Disassembler>) {
-        while !self.halted {
-            if let Some(ref mut dis) = maybe_dis {
-                dis.print_state(&self);
-                dis.print_dis(&self);
+    pub fn tick_timers(&mut self) {
+        self.dt.tick();
+        self.st.tick();
+        self.frames += 1;
+    }
+
+    pub fn sound_active(&self) -> bool {
+        self.st.get() > 0
+    }
+
+    // Runs one frame's worth of instructions followed by one 60Hz timer tick
+    pub fn run_frame(&mut self) {
+        self.run_frame_with(|_| {});
+    }
+
+    // Calls `each` before every instruction that is about to run
+    pub fn run_frame_with<F: FnMut(&Self)>(&mut self, mut each: F) {
+        match self.timing {
+            Timing::Ipf => {
+                for _ in 0..self.ipf {
+                    if self.is_running() {
+                        each(self);
+                    }
+
+                    self.step();
+                }
+            }
+            Timing::Vip => {
+                self.cycle_budget += timing::VIP_FRAME_CYCLES as i64;
+                let mut first = true;
+
+                while self.cycle_budget > 0 && self.is_running() {
+                    // DRW waits for the next vertical blank before drawing, so it only runs first
+                    // thing in a frame and the rest of this one goes idle
+                    if self.mem.read_u16(self.pc) & 0xf000 == 0xd000 && !first {
+                        self.cycle_budget = 0;
+                        break;
+                    }
+
+                    each(self);
+
+                    self.cycle_budget -= self.step_cycles() as i64;
+                    first = false;
+                }
+
+                // Cycles spent halted or waiting for a key don't carry over
+                self.cycle_budget = self.cycle_budget.min(0);
             }
+        }
+
+        self.tick_timers();
+    }
+
+    pub fn step(&mut self) {
+        self.step_cycles();
+    }
+
+    // Returns the VIP machine cycles the instruction took, 0 when nothing ran
+    fn step_cycles(&mut self) -> u32 {
+        if !self.is_running() {
+            return 0;
+        }
+
+        self.delay_wait = false;
+
+        let (op, _, helper) = self.read_decode();
+        let (pc, vx) = (self.pc, self.registers[helper.x]);
 
-            thread::sleep(self.cycle_delay_ms);
+        if self.breakpoints.contains(&pc) && !std::mem::take(&mut self.resuming) {
+            self.break_hit = Some(pc);
+            return 0;
+        }
+        self.resuming = false;
+
+        if !self.check_access(pc, Access::Execute, pc) {
+            return 0;
+        }
+
+        self.instructions += 1;
+
+        if let Some(ref mut coverage) = self.coverage {
+            coverage.mark_executed(pc);
+        }
+        if let Some(ref mut smc) = self.smc {
+            smc.mark_executed(pc);
+        }
+
+        let depth = self.mem.stack().len();
+        self.execute();
+
+        let cycles = timing::vip_cycles(op, vx, self.pc == pc.wrapping_add(4));
 
-            self.execute();
+        // CALL and RET show up as the stack changing depth
+        if let Some(ref mut profiler) = self.profiler {
+            profiler.record(pc, op, cycles, depth, self.mem.stack().len(), self.pc);
+        }
+
+        cycles
+    }
+
+    // I plus `offset`, wrapped around the end of RAM
+    fn index(&self, offset: u16) -> u16 {
+        (self.ir.wrapping_add(offset) as usize % self.mem.ram.len()) as u16
+    }
+
+    fn logic_vf_reset(&mut self) {
+        if self.quirks.vf_reset {
+            self.registers[0xf] = 0;
+        }
+    }
+
+    fn shift_source(&self, helper: &InstHelper) -> u8 {
+        if self.quirks.shifting {
+            self.registers[helper.x]
+        } else {
+            self.registers[helper.y]
         }
     }
 
     fn execute(&mut self) {
         let (raw, decoded, helper) = self.read_decode();
 
-        self.pc += 2;
+        self.pc = self.pc.wrapping_add(2);
+
+        let (chip8e, chip8x) = (
+            self.platform == Platform::Chip8E,
+            self.platform == Platform::Chip8X,
+        );
+        let big_font = self.platform.has_big_font();
 
         match decoded {
-            (0, 0, 0xe, 0) => {
-                self.mem.clear_display();
-                display::write_display(&self.mem.display);
+            (0, 0, 0xe, 0xd) if chip8e => self.halt(),
+            (0, 0, 0xf, 2) if chip8e => {}
+            (0, 1, 5, 1) if chip8e => {
+                // Spins on itself until DT runs out
+                if self.dt.get() > 0 {
+                    self.pc = self.pc.wrapping_sub(2);
+                }
             }
-            (0, 0, 0xe, 0xe) => {
-                if let Some(v) = self.mem.stack.pop() {
-                    self.pc = v;
-                } else {
-                    panic!("RET: stack underflow");
+            (0, 1, 8, 8) if chip8e => self.pc = self.pc.wrapping_add(2),
+            (0, 2, 0xa, 0) if chip8x => {
+                if let Some(ref mut map) = self.colour_map {
+                    map.step_background();
+                }
+                self.redraw = true;
+            }
+            (5, _, _, 1) if chip8e => {
+                if self.registers[helper.x] > self.registers[helper.y] {
+                    self.pc = self.pc.wrapping_add(2);
                 }
             }
-            (0, _, _, _) => self.halted = true, //std::process::exit(0),
+            (5, _, _, 1) if chip8x => {
+                // Each nibble is added on its own, wrapping at 8 like a colour value
+                let (x, y) = (self.registers[helper.x], self.registers[helper.y]);
+                self.registers[helper.x] =
+                    (((x >> 4) + (y >> 4)) % 8) << 4 | (((x & 0xf) + (y & 0xf)) % 8);
+            }
+            (5, _, _, 2) if chip8e => {
+                for (i, reg) in (helper.x.min(helper.y)..=helper.x.max(helper.y)).enumerate() {
+                    self.write_mem(self.index(i as u16), self.registers[reg]);
+                }
+            }
+            (5, _, _, 3) if chip8e => {
+                for (i, reg) in (helper.x.min(helper.y)..=helper.x.max(helper.y)).enumerate() {
+                    self.registers[reg] = self.read_mem(self.index(i as u16));
+                }
+            }
+            (0xb, 0xb, _, _) if chip8e => self.pc = self.pc.wrapping_sub(helper.nn as u16),
+            (0xb, 0xf, _, _) if chip8e => self.pc = self.pc.wrapping_add(helper.nn as u16),
+            (0xb, _, _, n) if chip8x => {
+                let (x, y) = (
+                    self.registers[helper.x],
+                    self.registers[(helper.x + 1) & 0xf],
+                );
+                let colour = self.registers[helper.y];
+
+                if let Some(ref mut map) = self.colour_map {
+                    map.set_zone(x, y, n, colour);
+                }
+                self.redraw = true;
+            }
+            // There is no second keypad, so none of its keys are ever down
+            (0xe, _, 0xf, 2) if chip8x => {}
+            (0xe, _, 0xf, 5) if chip8x => self.pc = self.pc.wrapping_add(2),
+            // Nothing is attached to the I/O ports, output is dropped and input reads as 0
+            (0xf, _, 0, 3) if chip8e => {}
+            (0xf, _, 0xe, 3) | (0xf, _, 0xe, 7) if chip8e => self.registers[helper.x] = 0,
+            (0xf, _, 0xf, 8) if chip8x => {}
+            (0xf, _, 0xf, 0xb) if chip8x => self.registers[helper.x] = 0,
+            (0xf, _, 1, 0xb) if chip8e => {
+                self.pc = self.pc.wrapping_add(self.registers[helper.x] as u16);
+            }
+            (0xf, _, 4, 0xf) if chip8e => {
+                self.dt.set(self.registers[helper.x]);
+                self.delay_wait = true;
+            }
+            (0, 0, 0xe, 0) => {
+                self.mem.clear_display();
+                self.redraw = true;
+            }
+            (0, 0, 0xe, 0xe) => match self.mem.pop_stack() {
+                Some(v) => self.pc = v,
+                None => self.fault(GuestError::StackUnderflow {
+                    pc: self.pc.wrapping_sub(2),
+                }),
+            },
+            // SCHIP and XO-CHIP use this space for their own opcodes instead of machine code
+            (0, _, _, _) if self.platform.is_supported() => self.halted = true, //std::process::exit(0),
             (1, _, _, _) => {
                 self.pc = helper.nnn;
             }
             (2, _, _, _) => {
-                self.mem.stack.push(self.pc);
-                self.pc = helper.nnn;
+                if self.mem.push_stack(self.pc) {
+                    self.pc = helper.nnn;
+                } else {
+                    self.fault(GuestError::StackOverflow {
+                        pc: self.pc.wrapping_sub(2),
+                        depth: self.mem.stack_depth(),
+                    });
+                }
             }
             (3, _, _, _) => {
                 if self.registers[helper.x] == helper.nn {
-                    self.pc += 2;
+                    self.pc = self.pc.wrapping_add(2);
                 }
             }
             (4, _, _, _) => {
                 if self.registers[helper.x] != helper.nn {
-                    self.pc += 2;
+                    self.pc = self.pc.wrapping_add(2);
                 }
             }
             (5, _, _, 0) => {
                 if self.registers[helper.x] == self.registers[helper.y] {
-                    self.pc += 2;
+                    self.pc = self.pc.wrapping_add(2);
                 }
             }
             (6, _, _, _) => self.registers[helper.x] = helper.nn,
@@ -423,9 +1056,18 @@ impl System {
                 self.registers[helper.x] = val;
             }
             (8, _, _, 0) => self.registers[helper.x] = self.registers[helper.y],
-            (8, _, _, 1) => self.registers[helper.x] |= self.registers[helper.y],
-            (8, _, _, 2) => self.registers[helper.x] &= self.registers[helper.y],
-            (8, _, _, 3) => self.registers[helper.x] ^= self.registers[helper.y],
+            (8, _, _, 1) => {
+                self.registers[helper.x] |= self.registers[helper.y];
+                self.logic_vf_reset();
+            }
+            (8, _, _, 2) => {
+                self.registers[helper.x] &= self.registers[helper.y];
+                self.logic_vf_reset();
+            }
+            (8, _, _, 3) => {
+                self.registers[helper.x] ^= self.registers[helper.y];
+                self.logic_vf_reset();
+            }
             (8, _, _, 4) => {
                 let (result, carry_flag) =
                     self.registers[helper.x].overflowing_add(self.registers[helper.y]);
@@ -441,9 +1083,10 @@ impl System {
                 self.registers[0xf] = !carry_flag as u8;
             }
             (8, _, _, 6) => {
-                self.registers[0xf] = self.registers[helper.y] & 0x1;
+                let source = self.shift_source(&helper);
 
-                self.registers[helper.x] = self.registers[helper.y] >> 1;
+                self.registers[helper.x] = source >> 1;
+                self.registers[0xf] = source & 0x1;
             }
             (8, _, _, 7) => {
                 let (result, carry_flag) =
@@ -453,77 +1096,102 @@ impl System {
                 self.registers[0xf] = !carry_flag as u8;
             }
             (8, _, _, 0xe) => {
-                self.registers[0xf] = (self.registers[helper.y] & 0x80) >> 7;
+                let source = self.shift_source(&helper);
 
-                self.registers[helper.x] = self.registers[helper.y] << 1;
+                self.registers[helper.x] = source << 1;
+                self.registers[0xf] = (source & 0x80) >> 7;
             }
             (9, _, _, 0) => {
                 if self.registers[helper.x] != self.registers[helper.y] {
-                    self.pc += 2;
+                    self.pc = self.pc.wrapping_add(2);
                 }
             }
             (0xa, _, _, _) => self.ir = helper.nnn,
-            (0xb, _, _, _) => self.pc = helper.nnn + self.registers[0] as u16,
+            (0xb, _, _, _) => {
+                let offset = if self.quirks.jumping {
+                    self.registers[helper.x]
+                } else {
+                    self.registers[0]
+                };
+
+                self.pc = helper.nnn + offset as u16;
+            }
             (0xc, _, _, _) => {
                 self.registers[helper.x] = 255; // TODO: finish RND
             }
             (0xd, _, _, n) => {
                 let mut buf = Vec::new();
                 for i in 0..n as u16 {
-                    buf.push(self.mem.read_u8(self.ir + i));
+                    buf.push(self.read_mem(self.index(i)));
                 }
 
-                let result =
-                    self.mem
-                        .write_sprite(self.registers[helper.x], self.registers[helper.y], &buf);
+                let result = self.mem.write_sprite(
+                    self.registers[helper.x],
+                    self.registers[helper.y],
+                    &buf,
+                    self.quirks.clipping,
+                );
 
                 self.registers[0xf] = result as u8;
 
-                display::write_display(&self.mem.display);
+                self.redraw = true;
             }
-            // TODO: finish HID instructions
             (0xe, _, 9, 0xe) => {
-                if scan_key(self.registers[helper.x]) {
-                    self.pc += 2;
+                if self.keys[(self.registers[helper.x] & 0xF) as usize] {
+                    self.pc = self.pc.wrapping_add(2);
                 }
             }
             (0xe, _, 0xa, 1) => {
-                if !scan_key(self.registers[helper.x]) {
-                    self.pc += 2;
+                if !self.keys[(self.registers[helper.x] & 0xF) as usize] {
+                    self.pc = self.pc.wrapping_add(2);
                 }
             }
             (0xf, _, 0, 7) => self.registers[helper.x] = self.dt.get(),
-            (0xf, _, 0, 0xa) => {
-                self.registers[helper.x] = wait_key();
-            }
+            (0xf, _, 0, 0xa) => self.wait_register = Some(helper.x),
             (0xf, _, 1, 5) => self.dt.set(self.registers[helper.x]),
-            (0xf, _, 1, 8) => self.st = self.registers[helper.x],
-            (0xf, _, 1, 0xe) => self.ir += self.registers[helper.x] as u16,
-            (0xf, _, 2, 9) => self.ir = (self.registers[helper.x] as u16 & 0xF) * 5,
+            (0xf, _, 1, 8) => self.st.set(self.registers[helper.x]),
+            (0xf, _, 1, 0xe) => self.ir = self.index(self.registers[helper.x] as u16),
+            (0xf, _, 2, 9) => {
+                let digit = self.registers[helper.x] as u16 & 0xF;
+                self.ir = self.mem.font_address + digit * SMALL_GLYPH_SIZE as u16;
+            }
+            (0xf, _, 3, 0) if big_font => {
+                let digit = self.registers[helper.x] as u16 & 0xF;
+                self.ir = self.mem.font_address
+                    + self.mem.font.big_offset()
+                    + digit * BIG_GLYPH_SIZE as u16;
+            }
             (0xf, _, 3, 3) => {
                 let x = self.registers[helper.x];
-                self.mem.write_u8(self.ir + 2, x % 10);
-                self.mem.write_u8(self.ir + 1, x / 10 % 10);
-                self.mem.write_u8(self.ir, x / 10 / 10);
+                self.write_mem(self.ir, x / 10 / 10);
+                self.write_mem(self.index(1), x / 10 % 10);
+                self.write_mem(self.index(2), x % 10);
             }
             (0xf, _, 5, 5) => {
                 for i in 0..=helper.x {
-                    self.mem.write_u8(self.ir + i as u16, self.registers[i]);
+                    self.write_mem(self.index(i as u16), self.registers[i]);
                 }
 
-                self.ir += helper.x as u16 + 1;
+                if self.quirks.memory {
+                    self.ir = self.index(helper.x as u16 + 1);
+                }
             }
             (0xf, _, 6, 5) => {
                 for i in 0..=helper.x {
-                    //self.mem.write_u8(self.ir + i as u16, self.registers[i]);
-                    self.registers[i] = self.mem.read_u8(self.ir + i as u16);
+                    //self.mem.write_u8(self.index(i as u16), self.registers[i]);
+                    self.registers[i] = self.read_mem(self.index(i as u16));
                 }
 
-                self.ir += helper.x as u16 + 1;
+                if self.quirks.memory {
+                    self.ir = self.index(helper.x as u16 + 1);
+                }
             }
             (0xf, 0xf, 0xf, 0xf) => self.halt(),
 
-            (_, _, _, _) => panic!("Unhandled op: {:#06x}", raw),
+            (_, _, _, _) => self.fault(GuestError::IllegalOpcode {
+                pc: self.pc.wrapping_sub(2),
+                op: raw,
+            }),
         }
     }
 }
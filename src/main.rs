@@ -8,9 +8,22 @@ use clap::clap_app;
 
 use colored::Colorize;
 
+use std::fs::File;
 use std::path::Path;
 
-use rusty_8::{dis, display};
+use rusty_8::flow::FlowGraph;
+use rusty_8::font::{Font, FontSet};
+use rusty_8::keymap::Keymap;
+use rusty_8::memmap::{Access, Policy};
+use rusty_8::palette::Palette;
+use rusty_8::platform::Platform;
+use rusty_8::rom::Rom;
+use rusty_8::script::Script;
+use rusty_8::symbols::Symbols;
+use rusty_8::timing::Timing;
+use rusty_8::{dis, display, rpc, term};
+
+const PHOSPHOR_FRAMES: u8 = 4;
 
 fn print_fatal<S: std::fmt::Display>(msg: S) -> ! {
     eprintln!("[{}]: {}", "rusty-8 error".red().bold(), msg);
@@ -33,6 +46,158 @@ fn read_file<P: AsRef<Path>>(path_ref: P) -> Vec<u8> {
     }
 }
 
+#[cfg(feature = "window")]
+fn run_window(
+    system: &mut rusty_8::System,
+    name: &str,
+    palette: Palette,
+    scale: usize,
+    keymap: &Keymap,
+    memory_log: Option<File>,
+) {
+    if let Err(e) = rusty_8::window::run(system, name, palette, scale, keymap, memory_log) {
+        print_fatal(e);
+    }
+}
+
+#[cfg(not(feature = "window"))]
+fn run_window(
+    _system: &mut rusty_8::System,
+    _name: &str,
+    _palette: Palette,
+    _scale: usize,
+    _keymap: &Keymap,
+    _memory_log: Option<File>,
+) {
+    print_fatal("built without the `window` feature");
+}
+
+// Parses `region.access=policy`, like `font.write=fault`
+fn parse_memory_policy(s: &str) -> Option<(&str, Access, Policy)> {
+    let (target, policy) = s.split_once('=')?;
+    let (region, access) = target.split_once('.')?;
+
+    let access = match access {
+        "write" => Access::Write,
+        "exec" => Access::Execute,
+        _ => return None,
+    };
+
+    Some((region, access, Policy::from_name(policy)?))
+}
+
+fn check_memory_policy(v: String) -> Result<(), String> {
+    if parse_memory_policy(&v).is_some() {
+        return Ok(());
+    }
+
+    Err(format!(
+        "`{}` should look like `region.write=policy` or `region.exec=policy`, where policy is one of {}",
+        v,
+        Policy::NAMES.join(", ")
+    ))
+}
+
+fn parse_hex(s: &str) -> Option<u16> {
+    u16::from_str_radix(s.trim_start_matches("0x"), 16).ok()
+}
+
+fn check_hex(v: String) -> Result<(), String> {
+    if parse_hex(&v).is_some() {
+        return Ok(());
+    }
+
+    Err(format!("`{}` is not a valid hex address", v))
+}
+
+// Summary, then the coverage map, then the annotated listing
+fn coverage_report(system: &rusty_8::System) -> String {
+    let coverage = system.coverage().unwrap();
+    let mut dis = dis::Disassembler::new();
+    dis.scan_labels(system);
+
+    format!(
+        "{}\n{}\n{}",
+        coverage.summary(system),
+        coverage.map(system),
+        coverage.listing(system, |addr| dis::strip_escapes(
+            &dis.mnemonic(system, addr)
+        ))
+    )
+}
+
+// Hotspots and the call tree, with the same disassembly as the debugger
+fn profile_report(system: &rusty_8::System) -> String {
+    let mut dis = dis::Disassembler::new();
+    dis.scan_labels(system);
+
+    system.profiler().unwrap().report(system, |addr| {
+        dis::strip_escapes(&dis.mnemonic(system, addr))
+    })
+}
+
+// Every store into code, with the patched instructions decoded as they are now
+fn smc_report(system: &rusty_8::System) -> String {
+    let mut dis = dis::Disassembler::new();
+    dis.scan_labels(system);
+
+    system.smc().unwrap().report(system, |addr| {
+        dis::strip_escapes(&dis.mnemonic(system, addr))
+    })
+}
+
+// Runs a script headless, paths in it are relative to the script
+fn run_script(path: &std::ffi::OsStr) {
+    let text = String::from_utf8_lossy(&read_file(path)).into_owned();
+    let base = Path::new(path).parent().unwrap_or_else(|| Path::new(""));
+
+    let script = match Script::parse(&text) {
+        Ok(script) => script,
+        Err(e) => print_fatal(format!("{}: {}", path.to_string_lossy(), e)),
+    };
+
+    let mut system = rusty_8::System::new(rusty_8::DEFAULT_IPF);
+
+    if let Err(e) = script.run(&mut system, base, &mut std::io::stdout()) {
+        print_fatal(format!("{}: {}", path.to_string_lossy(), e));
+    }
+}
+
+// Headless, the ROM is optional since it can be loaded over the protocol
+fn run_json_rpc(matches: &clap::ArgMatches<'_>) {
+    let ipf = match matches.value_of("ipf") {
+        Some(num_s) => num_s.parse::<usize>().unwrap(),
+        None => rusty_8::DEFAULT_IPF,
+    };
+
+    let mut system = rusty_8::System::new(ipf);
+    let platform = matches.value_of("platform").and_then(Platform::from_name);
+
+    if let Some(path) = matches.value_of_os("file") {
+        if let Err(e) = Rom::new(read_file(path)).load_into(&mut system, platform) {
+            print_fatal(format!("{}: {}", path.to_string_lossy(), e));
+        }
+    }
+
+    let stdin = std::io::stdin();
+    let result = rpc::Server::new().serve(&mut system, stdin.lock(), std::io::stdout());
+
+    if let Err(e) = result {
+        print_fatal(e);
+    }
+}
+
+// Symbols can only be checked once they have been loaded
+fn resolve_addr(system: &rusty_8::System, s: &str) -> u16 {
+    match system.symbols().resolve(s) {
+        Some(addr) => addr,
+        None => print_fatal(format!(
+            "`{}` is not a known symbol or a valid hex address",
+            s
+        )),
+    }
+}
+
 fn check_u64(v: String) -> Result<(), String> {
     if v.parse::<u64>().is_ok() {
         return Ok(());
@@ -45,39 +210,271 @@ fn main() {
     let matches = clap_app!(tmp =>
         (version: env!("CARGO_PKG_VERSION"))
         (about: env!("CARGO_PKG_DESCRIPTION"))
+        (@setting SubcommandsNegateReqs)
         (@arg debug: -D --debug "Enable debug output")
-        (@arg delay: --("cycle-sleep") +takes_value {check_u64} "Milliseconds to sleep between cycles (default 2, unless debug then 500)")
+        (@arg ipf: --ipf +takes_value {check_u64} "Instructions per 60Hz frame (default 10, or the ROM's tickrate)")
+        (@arg renderer: -r --renderer +takes_value possible_values(display::Renderer::NAMES) "Display renderer (default block)")
+        (@arg palette: -p --palette +takes_value "Colour palette, one of default, green, amber, lcd, contrast or a list of hex colours like 000000,ffffff (default default)")
+        (@arg phosphor: --phosphor "Fade out cleared pixels over a few frames to reduce flicker")
+        (@arg timing: --timing +takes_value possible_values(Timing::NAMES) "Instruction timing, a fixed rate per frame or COSMAC VIP machine cycles (default ipf)")
+        (@arg stack_depth: --("stack-depth") +takes_value {check_u64} "Nested calls allowed before a stack overflow (default 12 on chip8, 16 otherwise)")
+        (@arg memory_policy: --("memory-policy") +takes_value +multiple number_of_values(1) {check_memory_policy} "What to do on writes to or execution of a memory region, like font.write=fault (regions: font, interpreter, program, plus stack, variables and display on chip8)")
+        (@arg strict_memory: --("strict-memory") "Fault on any write to or execution of a reserved memory region")
+        (@arg memory_log: --("memory-log") +takes_value "File to record logged, warned and faulted memory accesses in")
+        (@arg font: --font +takes_value possible_values(FontSet::NAMES) "Built-in font glyphs (default vip on chip8, schip otherwise)")
+        (@arg font_file: --("font-file") +takes_value conflicts_with[font] "Load the font from a file of 80 small glyph bytes, optionally followed by 160 big glyph bytes (default SCHIP big glyphs)")
+        (@arg font_address: --("font-address") +takes_value {check_hex} "Hex address the font is loaded at (default 0x050)")
+        (@arg platform: --platform +takes_value possible_values(Platform::NAMES) "Platform to emulate (default picked from the ROM)")
+        (@arg load_address: --("load-address") +takes_value {check_hex} "Hex address the ROM is loaded at (default 0x200, 0x300 on chip8x, 0x600 on eti660)")
+        (@arg entry: --entry +takes_value "Hex address or symbol execution starts at (default the load address)")
+        (@arg breakpoint: -b --break +takes_value +multiple number_of_values(1) "Hex address or symbol to pause at before the instruction there runs")
+        (@arg symbols: --symbols +takes_value "Symbol file of `0x202 name` or Octo style `name = 0x202` lines, for the debugger")
+        (@arg window: -w --window "Open a window instead of drawing in the terminal (needs the `window` feature)")
+        (@arg scale: --scale +takes_value {check_u64} "Window pixels per CHIP-8 pixel (default 10)")
         (@arg disassemble: --disassemble "Perform disassembly instead of executing")
-        (@arg file: * +takes_value "Path to CHIP-8 ROM")
+        (@arg cfg: --cfg +takes_value "Write the ROM's control-flow graph to a Graphviz DOT file instead of running it")
+        (@arg coverage: --coverage +takes_value "Record which ROM bytes are executed, read and written, and write a report to this file on exit")
+        (@arg profile: --profile "Count executions per instruction and the cycles spent in each subroutine, and print a report on exit")
+        (@arg folded: --folded +takes_value "Profile the run and write its call stacks in the folded format flamegraph tools read to this file on exit")
+        (@arg smc: --smc +takes_value "Watch for stores into code that has run or runs later, and write a report of them to this file on exit")
+        (@arg json_rpc: --("json-rpc") "Take JSON-RPC requests one per line on stdin and answer on stdout instead of showing the display")
+        (@arg file: +takes_value required_unless[json_rpc] "Path to CHIP-8 ROM")
+        (@subcommand script =>
+            (about: "Run a script of commands that load a ROM, press keys, wait on and check the machine state and take screenshots")
+            (@arg script: * +takes_value "Path to the script")
+        )
     )
     .name("rusty-8")
     .get_matches();
 
+    if let Some(matches) = matches.subcommand_matches("script") {
+        run_script(matches.value_of_os("script").unwrap());
+        return;
+    }
+
+    if matches.is_present("json_rpc") {
+        run_json_rpc(&matches);
+        return;
+    }
+
     let debug = matches.is_present("debug");
-    let disassembly = matches.is_present("disassemble");
 
     if matches.is_present("file") {
         let file_name = matches.value_of_os("file").unwrap();
 
-        let source = read_file(file_name);
+        let rom = Rom::new(read_file(file_name));
+        let info = &rom.info;
 
-        let cycle_delay = match matches.value_of("delay") {
-            Some(num_s) => num_s.parse::<u64>().unwrap(),
-            None => 2,
+        let ipf = match matches.value_of("ipf") {
+            Some(num_s) => num_s.parse::<usize>().unwrap(),
+            None => info.tickrate.unwrap_or(rusty_8::DEFAULT_IPF),
         };
 
-        let mut system = rusty_8::System::new(cycle_delay);
+        let renderer = matches
+            .value_of("renderer")
+            .and_then(display::Renderer::from_name)
+            .unwrap_or(display::Renderer::Block);
 
-        system.load_rom(&source);
-        display::init(file_name.to_str().unwrap());
+        let palette = match matches.value_of("palette") {
+            Some(name) => match Palette::from_name(name) {
+                Some(palette) => palette,
+                None => print_fatal(format!("`{}` is not a valid palette", name)),
+            },
+            None => info.palette.unwrap_or_default(),
+        };
+
+        let name = match info.title {
+            Some(ref title) => title.clone(),
+            None => file_name.to_string_lossy().into_owned(),
+        };
 
-        // CTRL+C signal handler
-        ctrlc::set_handler(rusty_8::handle_ctrlc).expect("Error setting break handler");
+        let mut system = rusty_8::System::new(ipf);
 
-        if debug {
-            system.run(Some(&mut dis::Disassembler::new()))
+        // The ROM's quirks only apply when running on the platform it was made for
+        match matches.value_of("platform").and_then(Platform::from_name) {
+            Some(platform) => system.set_platform(platform),
+            None => {
+                if let Err(e) = rom.check_platform() {
+                    print_fatal(format!(
+                        "{}: {}, pick one with --platform to run it anyway",
+                        file_name.to_string_lossy(),
+                        e
+                    ));
+                }
+
+                system.set_platform(info.platform);
+                system.set_quirks(info.quirks);
+            }
+        }
+
+        if let Some(address) = matches.value_of("load_address").and_then(parse_hex) {
+            system.set_load_address(address);
+        }
+
+        if let Some(path) = matches.value_of_os("symbols") {
+            let text = String::from_utf8_lossy(&read_file(path)).into_owned();
+
+            match Symbols::parse(&text) {
+                Ok(symbols) => system.set_symbols(symbols),
+                Err(e) => print_fatal(format!("{}: {}", path.to_string_lossy(), e)),
+            }
+        }
+
+        if let Some(entry) = matches.value_of("entry") {
+            system.set_entry_address(resolve_addr(&system, entry));
+        }
+
+        let font = match (matches.value_of("font"), matches.value_of_os("font_file")) {
+            (Some(name), _) => FontSet::from_name(name).map(|set| set.font()),
+            (_, Some(path)) => match Font::from_bytes(&read_file(path)) {
+                Some(font) => Some(font),
+                None => print_fatal(format!(
+                    "{}: font files must be 80 or 240 bytes",
+                    path.to_string_lossy()
+                )),
+            },
+            (None, None) => None,
+        };
+
+        let font_address = matches.value_of("font_address").and_then(parse_hex);
+
+        if font.is_some() || font_address.is_some() {
+            let font = font.unwrap_or_else(|| system.memory().font().clone());
+            let address = font_address.unwrap_or_else(|| system.memory().font_address());
+
+            if !system.set_font(font, address) {
+                print_fatal(format!(
+                    "the font doesn't fit between {:#05x} and the program",
+                    address
+                ));
+            }
+        }
+
+        if matches.is_present("strict_memory") {
+            system.memory_map_mut().set_strict();
+        }
+
+        for policy in matches.values_of("memory_policy").into_iter().flatten() {
+            let (region, access, policy) = parse_memory_policy(policy).unwrap();
+
+            if !system.memory_map_mut().set_policy(region, access, policy) {
+                print_fatal(format!("no `{}` memory region on this platform", region));
+            }
+        }
+
+        let memory_log = matches
+            .value_of_os("memory_log")
+            .map(|path| File::create(path).unwrap_or_else(|e| print_fatal(e)));
+
+        if let Some(num_s) = matches.value_of("stack_depth") {
+            system.set_stack_depth(num_s.parse::<usize>().unwrap());
+        }
+
+        if let Some(timing) = matches.value_of("timing").and_then(Timing::from_name) {
+            system.set_timing(timing);
+        }
+
+        if let Err(e) = system.load_rom(&rom.data) {
+            print_fatal(format!("{}: {}", file_name.to_string_lossy(), e));
+        }
+
+        for addr in matches.values_of("breakpoint").into_iter().flatten() {
+            system.set_breakpoint(resolve_addr(&system, addr), true);
+        }
+
+        if let Some(path) = matches.value_of_os("cfg") {
+            let mut dis = dis::Disassembler::new();
+            dis.scan_labels(&system);
+
+            let graph = FlowGraph::build(&system);
+            let dot = graph.to_dot(&system, |addr| {
+                dis::strip_escapes(&dis.mnemonic(&system, addr))
+            });
+
+            if let Err(e) = std::fs::write(path, dot) {
+                print_fatal(format!("{}: {}", path.to_string_lossy(), e));
+            }
+
+            return;
+        }
+
+        if matches.is_present("coverage") {
+            system.enable_coverage();
+        }
+
+        if matches.is_present("profile") || matches.is_present("folded") {
+            system.enable_profiling();
+        }
+
+        if matches.is_present("smc") {
+            system.enable_smc_detection();
+        }
+
+        if matches.is_present("window") {
+            let scale = match matches.value_of("scale") {
+                Some(num_s) => num_s.parse::<usize>().unwrap(),
+                None => 10,
+            };
+
+            run_window(&mut system, &name, palette, scale, &info.keymap, memory_log);
         } else {
-            system.run(None);
+            let mut screen = display::Screen::new(&name, renderer, debug);
+            screen.set_palette(palette);
+
+            if matches.is_present("phosphor") {
+                screen.set_phosphor(PHOSPHOR_FRAMES);
+            }
+
+            // The handlers only set the flags, the run loop acts on them
+            signal_hook::flag::register(signal_hook::consts::SIGWINCH, screen.resize_flag())
+                .expect("Error setting resize handler");
+            signal_hook::flag::register(signal_hook::consts::SIGINT, screen.interrupt_flag())
+                .expect("Error setting break handler");
+
+            if debug {
+                term::run(
+                    &mut system,
+                    screen,
+                    Some(dis::Disassembler::new()),
+                    &info.keymap,
+                    memory_log,
+                );
+            } else {
+                term::run(&mut system, screen, None, &info.keymap, memory_log);
+            }
+        }
+
+        if let Some(path) = matches.value_of_os("coverage") {
+            if let Err(e) = std::fs::write(path, coverage_report(&system)) {
+                print_fatal(format!("{}: {}", path.to_string_lossy(), e));
+            }
+        }
+
+        if let Some(path) = matches.value_of_os("smc") {
+            if let Err(e) = std::fs::write(path, smc_report(&system)) {
+                print_fatal(format!("{}: {}", path.to_string_lossy(), e));
+            }
+        }
+
+        if let Some(path) = matches.value_of_os("folded") {
+            let folded = system.profiler().unwrap().folded(&system);
+
+            if let Err(e) = std::fs::write(path, folded) {
+                print_fatal(format!("{}: {}", path.to_string_lossy(), e));
+            }
+        }
+
+        if matches.is_present("profile") {
+            println!(
+                "\x1b[?7h{}{}",
+                termion::cursor::Show,
+                profile_report(&system)
+            );
+        }
+
+        if let Some(e) = system.error() {
+            println!("\x1b[?7h{}", termion::cursor::Show);
+            print_fatal(system.symbols().annotate(e, e.pc()));
         }
     } else {
         println!("Nothing to do.");
@@ -148,5 +545,5 @@ fn main() {
 
     disassembler.print_state();*/
 
-    println!("{}", termion::cursor::Show);
+    println!("\x1b[?7h{}", termion::cursor::Show);
 }
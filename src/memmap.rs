@@ -0,0 +1,207 @@
+// The MIT License (MIT)
+
+// Copyright (c) 2021 AnonymousDapper
+
+use std::fmt;
+
+use super::platform::Platform;
+use super::STACK_AREA_SIZE;
+
+// What happens when a ROM writes to, or jumps into, a region
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Policy {
+    Allow,
+    Log,   // Recorded for the memory log
+    Warn,  // Recorded and shown to the user
+    Fault, // Halts with a guest error
+}
+
+impl Policy {
+    pub const NAMES: &'static [&'static str] = &["allow", "log", "warn", "fault"];
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "allow" => Some(Self::Allow),
+            "log" => Some(Self::Log),
+            "warn" => Some(Self::Warn),
+            "fault" => Some(Self::Fault),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Access {
+    Write,
+    Execute,
+}
+
+impl fmt::Display for Access {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Write => write!(f, "write to"),
+            Self::Execute => write!(f, "execution of"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Region {
+    pub name: &'static str,
+    pub start: usize,
+    pub end: usize,     // Exclusive
+    pub reserved: bool, // Not meant for the program's own code and data
+    pub write: Policy,
+    pub execute: Policy,
+}
+
+impl Region {
+    pub fn contains(&self, addr: u16) -> bool {
+        (self.start..self.end).contains(&(addr as usize))
+    }
+
+    pub fn policy(&self, access: Access) -> Policy {
+        match access {
+            Access::Write => self.write,
+            Access::Execute => self.execute,
+        }
+    }
+}
+
+// A write or jump that hit a region with a policy other than `Allow`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct MemoryEvent {
+    pub access: Access,
+    pub addr: u16,
+    pub pc: u16,
+    pub region: &'static str,
+    pub policy: Policy,
+}
+
+impl fmt::Display for MemoryEvent {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} {} at {:#06x} from {:#06x}",
+            self.access, self.region, self.addr, self.pc
+        )
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MemoryMap {
+    regions: Vec<Region>,
+}
+
+impl MemoryMap {
+    pub fn for_platform(platform: Platform) -> Self {
+        let start = platform.load_address() as usize;
+        let size = platform.memory_size();
+
+        let reserved = |name, start, end, write| Region {
+            name,
+            start,
+            end,
+            reserved: true,
+            write,
+            execute: Policy::Warn,
+        };
+
+        let program = |end| Region {
+            name: "program",
+            start,
+            end,
+            reserved: false,
+            write: Policy::Allow,
+            execute: Policy::Allow,
+        };
+
+        // Regions are matched in order, so the font takes precedence over the interpreter area around it
+        let font = platform.font_address() as usize;
+        let mut regions = vec![
+            reserved(
+                "font",
+                font,
+                font + platform.font_set().font().len(),
+                Policy::Warn,
+            ),
+            reserved("interpreter", 0x000, start, Policy::Warn),
+        ];
+
+        // The VIP interpreter keeps its stack, variables and the display buffer at the top of RAM
+        match platform.stack_base() {
+            Some(stack) => {
+                let stack = stack as usize;
+                let variables = stack + STACK_AREA_SIZE;
+
+                regions.extend([
+                    program(stack),
+                    reserved("stack", stack, variables, Policy::Log),
+                    reserved("variables", variables, 0xf00, Policy::Log),
+                    reserved("display", 0xf00, size, Policy::Log),
+                ]);
+            }
+            None => regions.push(program(size)),
+        }
+
+        Self { regions }
+    }
+
+    pub fn regions(&self) -> &[Region] {
+        &self.regions
+    }
+
+    pub fn region(&self, addr: u16) -> Option<&Region> {
+        self.regions.iter().find(|region| region.contains(addr))
+    }
+
+    pub fn policy(&self, addr: u16, access: Access) -> Policy {
+        self.region(addr)
+            .map_or(Policy::Allow, |region| region.policy(access))
+    }
+
+    pub fn set_font_area(&mut self, start: usize, end: usize) {
+        for region in self
+            .regions
+            .iter_mut()
+            .filter(|region| region.name == "font")
+        {
+            region.start = start;
+            region.end = end;
+        }
+    }
+
+    // The interpreter area ends where the program starts
+    pub fn set_program_start(&mut self, start: usize) {
+        for region in self.regions.iter_mut() {
+            match region.name {
+                "interpreter" => region.end = start,
+                "program" => region.start = start,
+                _ => {}
+            }
+        }
+    }
+
+    // Returns false if there is no region called `name`
+    pub fn set_policy(&mut self, name: &str, access: Access, policy: Policy) -> bool {
+        let mut found = false;
+
+        for region in self.regions.iter_mut().filter(|region| region.name == name) {
+            match access {
+                Access::Write => region.write = policy,
+                Access::Execute => region.execute = policy,
+            }
+            found = true;
+        }
+
+        found
+    }
+
+    // Every reserved region faults on both writes and execution
+    pub fn set_strict(&mut self) {
+        for region in self.regions.iter_mut().filter(|region| region.reserved) {
+            region.write = Policy::Fault;
+            region.execute = Policy::Fault;
+        }
+    }
+}
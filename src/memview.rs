@@ -0,0 +1,211 @@
+// The MIT License (MIT)
+
+// Copyright (c) 2021 AnonymousDapper
+
+use std::collections::VecDeque;
+use std::io::Write;
+use std::ops::Range;
+
+use super::layout::Rect;
+use super::platform::Platform;
+use super::System;
+
+pub const BYTES_PER_ROW: usize = 8;
+
+// How long a written byte stays marked
+const RECENT_FRAMES: u64 = 30;
+const MAX_RECENT_WRITES: usize = 256;
+
+// The bytes the instruction at PC is about to touch, and whether it writes them
+pub fn pending_access(system: &System) -> Option<(Range<u16>, bool)> {
+    let (_, decoded, helper) = system.read_decode();
+    let ir = system.ir;
+    let span = |len: usize| ir..ir.saturating_add(len as u16);
+    let registers = helper.x.abs_diff(helper.y) + 1;
+
+    match decoded {
+        (0xd, _, _, n) => Some((span(n as usize), false)),
+        (0xf, _, 3, 3) => Some((span(3), true)),
+        (0xf, _, 5, 5) => Some((span(helper.x + 1), true)),
+        (0xf, _, 6, 5) => Some((span(helper.x + 1), false)),
+        (5, _, _, 2) if system.platform == Platform::Chip8E => Some((span(registers), true)),
+        (5, _, _, 3) if system.platform == Platform::Chip8E => Some((span(registers), false)),
+        _ => None,
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct Cursor {
+    addr: u16,
+    high: Option<u8>, // First nibble typed, waiting on the second
+}
+
+// Hex dump of RAM that follows I, or a cursor while editing
+#[derive(Debug)]
+pub struct MemoryView {
+    top: u16,
+    cursor: Option<Cursor>,
+    written: VecDeque<(u16, u64)>, // Address and the frame it was written on
+}
+
+impl MemoryView {
+    #[allow(clippy::new_without_default)]
+    pub fn new() -> Self {
+        Self {
+            top: 0,
+            cursor: None,
+            written: VecDeque::new(),
+        }
+    }
+
+    pub fn is_editing(&self) -> bool {
+        self.cursor.is_some()
+    }
+
+    // Editing starts at I
+    pub fn set_editing(&mut self, system: &System, editing: bool) {
+        self.cursor = if editing {
+            Some(Cursor {
+                addr: system.ir,
+                high: None,
+            })
+        } else {
+            None
+        };
+    }
+
+    // Remembers what the instruction at PC writes, call before it runs
+    pub fn trace(&mut self, system: &System) {
+        if let Some((range, true)) = pending_access(system) {
+            for addr in range {
+                self.mark_written(addr, system.frames());
+            }
+        }
+    }
+
+    fn mark_written(&mut self, addr: u16, frame: u64) {
+        if self.written.len() >= MAX_RECENT_WRITES {
+            self.written.pop_front();
+        }
+        self.written.push_back((addr, frame));
+    }
+
+    // Arrow keys move the cursor, hex digits overwrite the byte under it and ESC stops editing.
+    // Returns false once editing has stopped
+    pub fn input(&mut self, system: &mut System, bytes: &[u8]) -> bool {
+        let size = system.mem.ram.len() as i32;
+        let mut i = 0;
+
+        while let Some(mut cursor) = self.cursor {
+            let addr = cursor.addr as i32;
+            let move_by = |offset: i32| (addr + offset).rem_euclid(size) as u16;
+
+            match bytes[i..] {
+                [] => break,
+                [0x1b, b'[', key, ..] => {
+                    i += 2;
+                    cursor.high = None;
+                    cursor.addr = match key {
+                        b'A' => move_by(-(BYTES_PER_ROW as i32)),
+                        b'B' => move_by(BYTES_PER_ROW as i32),
+                        b'C' => move_by(1),
+                        b'D' => move_by(-1),
+                        _ => cursor.addr,
+                    };
+                }
+                [0x1b, ..] => {
+                    self.cursor = None;
+                    break;
+                }
+                [byte, ..] => {
+                    if let Some(digit) = (byte as char).to_digit(16) {
+                        let digit = digit as u8;
+
+                        match cursor.high.take() {
+                            Some(high) => {
+                                system.mem.write_u8(cursor.addr, high << 4 | digit);
+                                self.mark_written(cursor.addr, system.frames());
+                                cursor.addr = move_by(1);
+                            }
+                            None => cursor.high = Some(digit),
+                        }
+                    }
+                }
+            }
+
+            i += 1;
+            self.cursor = Some(cursor);
+        }
+
+        self.is_editing()
+    }
+
+    pub fn print(&mut self, system: &System, pane: Rect, out: &mut dyn Write) {
+        let mut buffer = format!(
+            "\x1b[{};{}H\x1b[32;40;4;1m< Memory >\x1b[0m",
+            pane.row, pane.col
+        );
+
+        let rows = pane.height.saturating_sub(2);
+        let row_bytes = BYTES_PER_ROW as u16;
+        let size = system.mem.ram.len();
+
+        // Scrolls only as far as needed to keep the cursor in view, otherwise I sits on the top row
+        let (status, focus) = match self.cursor {
+            Some(cursor) => (
+                format!("\x1b[91mEDIT\x1b[0m {:#06x}", cursor.addr),
+                cursor.addr,
+            ),
+            None => (format!("\x1b[33mI\x1b[0m    {:#06x}", system.ir), system.ir),
+        };
+        let focus = focus & !(row_bytes - 1);
+        let window = (rows as u16).saturating_sub(1) * row_bytes;
+
+        self.top = match self.cursor {
+            Some(_) if focus < self.top => focus,
+            Some(_) if focus > self.top.saturating_add(window) => focus - window,
+            Some(_) => self.top,
+            None => focus,
+        };
+
+        buffer.push_str(&format!("\x1b[{};{}H{}", pane.row + 1, pane.col, status));
+
+        let frame = system.frames();
+        self.written
+            .retain(|&(_, written)| frame.saturating_sub(written) < RECENT_FRAMES);
+
+        let pending = pending_access(system).map(|(range, _)| range);
+
+        for row in 0..rows {
+            let start = self.top as usize + row * BYTES_PER_ROW;
+            buffer.push_str(&format!("\x1b[{};{}H", pane.row + 2 + row, pane.col));
+
+            if start >= size {
+                buffer.push_str(&format!("{:width$}", "", width = pane.width));
+                continue;
+            }
+
+            buffer.push_str(&format!("\x1b[90m{:#06x}\x1b[0m ", start));
+
+            for addr in (start..start + BYTES_PER_ROW).map(|addr| addr as u16) {
+                let colour = if self.cursor.map(|cursor| cursor.addr) == Some(addr) {
+                    "7"
+                } else if pending.as_ref().is_some_and(|range| range.contains(&addr)) {
+                    "30;103"
+                } else if self.written.iter().any(|&(written, _)| written == addr) {
+                    "91"
+                } else {
+                    "37"
+                };
+
+                buffer.push_str(&format!(
+                    " \x1b[{}m{:02x}\x1b[0m",
+                    colour,
+                    system.mem.read_u8(addr)
+                ));
+            }
+        }
+
+        write!(out, "{}", buffer).ok();
+    }
+}
@@ -0,0 +1,162 @@
+// The MIT License (MIT)
+
+// Copyright (c) 2021 AnonymousDapper
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Colour {
+    Ansi(u8), // SGR foreground code, background is code + 10
+    Rgb(u8, u8, u8),
+}
+
+impl Colour {
+    pub fn fg(&self) -> String {
+        match self {
+            Self::Ansi(code) => code.to_string(),
+            Self::Rgb(r, g, b) => format!("38;2;{};{};{}", r, g, b),
+        }
+    }
+
+    pub fn bg(&self) -> String {
+        match self {
+            Self::Ansi(code) => (code + 10).to_string(),
+            Self::Rgb(r, g, b) => format!("48;2;{};{};{}", r, g, b),
+        }
+    }
+
+    pub fn rgb(&self) -> (u8, u8, u8) {
+        match self {
+            Self::Rgb(r, g, b) => (*r, *g, *b),
+            // xterm defaults for the 16 standard colours
+            Self::Ansi(code) => match code {
+                30 => (0x00, 0x00, 0x00),
+                31 => (0xcd, 0x00, 0x00),
+                32 => (0x00, 0xcd, 0x00),
+                33 => (0xcd, 0xcd, 0x00),
+                34 => (0x00, 0x00, 0xee),
+                35 => (0xcd, 0x00, 0xcd),
+                36 => (0x00, 0xcd, 0xcd),
+                37 => (0xe5, 0xe5, 0xe5),
+                90 => (0x7f, 0x7f, 0x7f),
+                91 => (0xff, 0x00, 0x00),
+                92 => (0x00, 0xff, 0x00),
+                93 => (0xff, 0xff, 0x00),
+                94 => (0x5c, 0x5c, 0xff),
+                95 => (0xff, 0x00, 0xff),
+                96 => (0x00, 0xff, 0xff),
+                _ => (0xff, 0xff, 0xff),
+            },
+        }
+    }
+
+    // `amount` of 0 is `self`, 255 is `other`
+    pub fn blend(self, other: Self, amount: u8) -> Self {
+        match (self, other) {
+            (Self::Rgb(r1, g1, b1), Self::Rgb(r2, g2, b2)) => {
+                let mix = |a: u8, b: u8| {
+                    ((a as u16 * (255 - amount) as u16 + b as u16 * amount as u16) / 255) as u8
+                };
+
+                Self::Rgb(mix(r1, r2), mix(g1, g2), mix(b1, b2))
+            }
+            // 16 colour terminals only get a single grey step in between
+            _ => match amount {
+                0 => self,
+                255 => other,
+                _ => Self::Ansi(90),
+            },
+        }
+    }
+
+    // Accepts `rrggbb` or `#rrggbb`
+    pub fn from_hex(s: &str) -> Option<Self> {
+        let hex = s.trim().trim_start_matches('#');
+
+        if hex.len() != 6 || !hex.chars().all(|c| c.is_ascii_hexdigit()) {
+            return None;
+        }
+
+        let channel = |i: usize| u8::from_str_radix(&hex[i..i + 2], 16).ok();
+
+        Some(Self::Rgb(channel(0)?, channel(2)?, channel(4)?))
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Palette {
+    // Indexed by pixel value: off, plane 1, plane 2, both planes
+    pub colours: [Colour; 4],
+}
+
+impl Palette {
+    pub const NAMES: &'static [&'static str] = &["default", "green", "amber", "lcd", "contrast"];
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        use Colour::*;
+
+        let colours = match name {
+            "default" => [Ansi(30), Ansi(97), Ansi(93), Ansi(96)],
+            "green" => [
+                Rgb(0x04, 0x14, 0x08),
+                Rgb(0x33, 0xff, 0x66),
+                Rgb(0x1a, 0x99, 0x3d),
+                Rgb(0xb3, 0xff, 0xc6),
+            ],
+            "amber" => [
+                Rgb(0x16, 0x0c, 0x00),
+                Rgb(0xff, 0xb0, 0x00),
+                Rgb(0x99, 0x5c, 0x00),
+                Rgb(0xff, 0xe0, 0x99),
+            ],
+            "lcd" => [
+                Rgb(0x9b, 0xbc, 0x0f),
+                Rgb(0x0f, 0x38, 0x0f),
+                Rgb(0x30, 0x62, 0x30),
+                Rgb(0x8b, 0xac, 0x0f),
+            ],
+            "contrast" => [
+                Rgb(0x00, 0x00, 0x00),
+                Rgb(0xff, 0xff, 0xff),
+                Rgb(0xff, 0xff, 0x00),
+                Rgb(0x00, 0xff, 0xff),
+            ],
+            _ => return Self::from_hex_list(name),
+        };
+
+        Some(Self { colours })
+    }
+
+    // Custom palette as a comma separated list of 2 to 4 hex colours, starting with the background
+    pub fn from_hex_list(list: &str) -> Option<Self> {
+        let parsed = list
+            .split(',')
+            .map(Colour::from_hex)
+            .collect::<Option<Vec<_>>>()?;
+
+        if parsed.len() < 2 || parsed.len() > 4 {
+            return None;
+        }
+
+        let mut colours = [parsed[1]; 4];
+        colours[0] = parsed[0];
+
+        for (i, colour) in parsed.iter().enumerate().skip(2) {
+            colours[i] = *colour;
+        }
+
+        Some(Self { colours })
+    }
+
+    pub fn background(&self) -> Colour {
+        self.colours[0]
+    }
+
+    pub fn pixel(&self, value: u8) -> Colour {
+        self.colours[(value & 0x3) as usize]
+    }
+}
+
+impl Default for Palette {
+    fn default() -> Self {
+        Self::from_name("default").unwrap()
+    }
+}
@@ -0,0 +1,175 @@
+// The MIT License (MIT)
+
+// Copyright (c) 2021 AnonymousDapper
+
+use super::font::FontSet;
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Platform {
+    #[default]
+    Chip8, // COSMAC VIP
+    Chip8E,    // VIP with Gene Fisher's extra opcodes
+    Chip8X,    // VIP with the VP-590 colour board
+    Eti660,    // ETI-660 Learning Computer
+    SuperChip, // SCHIP 1.1 on the HP-48
+    XoChip,
+}
+
+impl Platform {
+    pub const NAMES: &'static [&'static str] =
+        &["chip8", "chip8e", "chip8x", "eti660", "schip", "xochip"];
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "chip8" => Some(Self::Chip8),
+            "chip8e" => Some(Self::Chip8E),
+            "chip8x" => Some(Self::Chip8X),
+            "eti660" => Some(Self::Eti660),
+            "schip" => Some(Self::SuperChip),
+            "xochip" => Some(Self::XoChip),
+            _ => None,
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Chip8 => "chip8",
+            Self::Chip8E => "chip8e",
+            Self::Chip8X => "chip8x",
+            Self::Eti660 => "eti660",
+            Self::SuperChip => "schip",
+            Self::XoChip => "xochip",
+        }
+    }
+
+    // Variants that run on the VIP's own interpreter layout
+    pub fn is_vip(&self) -> bool {
+        matches!(self, Self::Chip8 | Self::Chip8E | Self::Chip8X)
+    }
+
+    // SCHIP and XO-CHIP only bring their quirks, memory layout and big font, the rest of their
+    // own opcodes don't run
+    pub fn is_supported(&self) -> bool {
+        !matches!(self, Self::SuperChip | Self::XoChip)
+    }
+
+    // Whether Fx30 points I at an 8x10 digit
+    pub fn has_big_font(&self) -> bool {
+        matches!(self, Self::SuperChip | Self::XoChip)
+    }
+
+    pub fn memory_size(&self) -> usize {
+        match self {
+            Self::XoChip => 0x10000,
+            _ => 0x1000,
+        }
+    }
+
+    // Where the ROM is loaded
+    pub fn load_address(&self) -> u16 {
+        match self {
+            Self::Chip8X => 0x300, // The colour interpreter takes up an extra page
+            Self::Eti660 => 0x600,
+            _ => 0x200,
+        }
+    }
+
+    // Where execution starts
+    pub fn entry_address(&self) -> u16 {
+        self.load_address()
+    }
+
+    // Subroutine levels before a CALL overflows
+    pub fn stack_depth(&self) -> usize {
+        match self {
+            Self::SuperChip | Self::XoChip => 16,
+            _ => 12,
+        }
+    }
+
+    // Where in RAM the stack lives, if the original interpreter kept it there
+    pub fn stack_base(&self) -> Option<u16> {
+        if self.is_vip() {
+            Some(0xea0)
+        } else {
+            None
+        }
+    }
+
+    pub fn font_set(&self) -> FontSet {
+        match self {
+            Self::Chip8 | Self::Chip8E | Self::Chip8X => FontSet::Vip,
+            Self::Eti660 => FontSet::Eti660,
+            Self::SuperChip | Self::XoChip => FontSet::Schip,
+        }
+    }
+
+    pub fn font_address(&self) -> u16 {
+        0x050
+    }
+
+    pub fn max_rom_size(&self) -> usize {
+        self.memory_size() - self.load_address() as usize
+    }
+
+    pub fn quirks(&self) -> Quirks {
+        match self {
+            Self::Chip8 | Self::Chip8E | Self::Chip8X | Self::Eti660 => Quirks {
+                vf_reset: true,
+                memory: true,
+                shifting: false,
+                jumping: false,
+                clipping: true,
+            },
+            Self::SuperChip => Quirks {
+                vf_reset: false,
+                memory: false,
+                shifting: true,
+                jumping: true,
+                clipping: true,
+            },
+            Self::XoChip => Quirks {
+                vf_reset: false,
+                memory: true,
+                shifting: false,
+                jumping: false,
+                clipping: false,
+            },
+        }
+    }
+}
+
+// Behaviours that differ between interpreters, named after the usual quirk tests
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Quirks {
+    pub vf_reset: bool, // 8xy1, 8xy2 and 8xy3 zero VF
+    pub memory: bool,   // Fx55 and Fx65 leave I pointing past the last register
+    pub shifting: bool, // 8xy6 and 8xyE shift Vx in place instead of Vy
+    pub jumping: bool,  // Bnnn jumps to xnn + Vx instead of nnn + V0
+    pub clipping: bool, // Sprites are clipped at the display edge instead of wrapping
+}
+
+impl Quirks {
+    pub const NAMES: &'static [&'static str] =
+        &["vf_reset", "memory", "shifting", "jumping", "clipping"];
+
+    pub fn set(&mut self, name: &str, enabled: bool) -> bool {
+        let flag = match name {
+            "vf_reset" => &mut self.vf_reset,
+            "memory" => &mut self.memory,
+            "shifting" => &mut self.shifting,
+            "jumping" => &mut self.jumping,
+            "clipping" => &mut self.clipping,
+            _ => return false,
+        };
+
+        *flag = enabled;
+        true
+    }
+}
+
+impl Default for Quirks {
+    fn default() -> Self {
+        Platform::default().quirks()
+    }
+}
@@ -0,0 +1,355 @@
+// The MIT License (MIT)
+
+// Copyright (c) 2021 AnonymousDapper
+
+use std::collections::BTreeMap;
+use std::fmt::Write;
+
+use super::System;
+
+// Rows in the hotspot table
+const HOTSPOTS: usize = 20;
+
+// Opcode classes by their first nibble
+const CLASSES: [&str; 16] = [
+    "0nnn system",
+    "1nnn jump",
+    "2nnn call",
+    "3xnn skip eq",
+    "4xnn skip ne",
+    "5xy_ registers",
+    "6xnn load",
+    "7xnn add",
+    "8xy_ arithmetic",
+    "9xy0 skip ne",
+    "Annn index",
+    "Bnnn jump v0",
+    "Cxnn random",
+    "Dxyn draw",
+    "Ex__ keys",
+    "Fx__ misc",
+];
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Cost {
+    pub instructions: u64,
+    pub cycles: u64, // COSMAC VIP machine cycles, whatever timing is in use
+}
+
+impl Cost {
+    fn add(&mut self, other: Cost) {
+        self.instructions += other.instructions;
+        self.cycles += other.cycles;
+    }
+}
+
+// One place in the call tree, the same subroutine called from two places gets two nodes
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CallNode {
+    pub addr: u16,
+    pub calls: u64,
+    pub own: Cost,
+    parent: Option<usize>,
+    children: BTreeMap<u16, usize>,
+}
+
+// Counts executions per instruction and per opcode class, and what each call costs from its
+// CALL through to the matching RET
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Profiler {
+    counts: Vec<Cost>,
+    classes: [Cost; 16],
+    nodes: Vec<CallNode>,
+    current: usize,
+    depth: usize,
+}
+
+impl Profiler {
+    // The root of the call tree is the code run from the entry point
+    pub fn new(size: usize, entry: u16) -> Self {
+        Self {
+            counts: vec![Cost::default(); size],
+            classes: [Cost::default(); 16],
+            nodes: vec![CallNode {
+                addr: entry,
+                calls: 1,
+                own: Cost::default(),
+                parent: None,
+                children: BTreeMap::new(),
+            }],
+            current: 0,
+            depth: 0,
+        }
+    }
+
+    // Call after the instruction at `pc` has run, with the stack depth before and after it and
+    // where it left PC
+    pub fn record(
+        &mut self,
+        pc: u16,
+        op: u16,
+        cycles: u32,
+        before: usize,
+        after: usize,
+        next: u16,
+    ) {
+        let cost = Cost {
+            instructions: 1,
+            cycles: cycles as u64,
+        };
+
+        // Restarts empty the stack without any RETs
+        while self.depth > before {
+            self.leave();
+        }
+
+        let len = self.counts.len();
+        self.counts[pc as usize % len].add(cost);
+        self.classes[(op >> 12) as usize].add(cost);
+        self.nodes[self.current].own.add(cost);
+
+        if after > before {
+            self.enter(next);
+        } else if after < before {
+            self.leave();
+        }
+    }
+
+    fn enter(&mut self, addr: u16) {
+        let node = match self.nodes[self.current].children.get(&addr) {
+            Some(&node) => node,
+            None => {
+                let node = self.nodes.len();
+                self.nodes.push(CallNode {
+                    addr,
+                    calls: 0,
+                    own: Cost::default(),
+                    parent: Some(self.current),
+                    children: BTreeMap::new(),
+                });
+                self.nodes[self.current].children.insert(addr, node);
+                node
+            }
+        };
+
+        self.nodes[node].calls += 1;
+        self.current = node;
+        self.depth += 1;
+    }
+
+    fn leave(&mut self) {
+        if let Some(parent) = self.nodes[self.current].parent {
+            self.current = parent;
+            self.depth -= 1;
+        }
+    }
+
+    pub fn count(&self, addr: u16) -> Cost {
+        self.counts.get(addr as usize).copied().unwrap_or_default()
+    }
+
+    pub fn class(&self, op: u16) -> Cost {
+        self.classes[(op >> 12) as usize]
+    }
+
+    pub fn root(&self) -> &CallNode {
+        &self.nodes[0]
+    }
+
+    pub fn children<'a>(&'a self, node: &'a CallNode) -> impl Iterator<Item = &'a CallNode> {
+        node.children.values().map(move |&child| &self.nodes[child])
+    }
+
+    // The node's own cost plus everything it called
+    pub fn total(&self, node: &CallNode) -> Cost {
+        let mut total = node.own;
+        let mut stack: Vec<usize> = node.children.values().copied().collect();
+
+        while let Some(child) = stack.pop() {
+            total.add(self.nodes[child].own);
+            stack.extend(self.nodes[child].children.values());
+        }
+
+        total
+    }
+
+    // Every node's total, indexed like `nodes`. Deep recursion in the guest makes for a call tree
+    // too deep to walk recursively, so children are summed into their parents in post-order
+    fn totals(&self) -> Vec<Cost> {
+        let mut totals: Vec<Cost> = self.nodes.iter().map(|node| node.own).collect();
+        let mut stack = vec![(0, false)];
+
+        while let Some((node, visited)) = stack.pop() {
+            if visited {
+                if let Some(parent) = self.nodes[node].parent {
+                    let total = totals[node];
+                    totals[parent].add(total);
+                }
+            } else {
+                stack.push((node, true));
+                stack.extend(
+                    self.nodes[node]
+                        .children
+                        .values()
+                        .map(|&child| (child, false)),
+                );
+            }
+        }
+
+        totals
+    }
+
+    fn name(&self, system: &System, addr: u16) -> String {
+        match system.symbols().name(addr) {
+            Some(name) => name.to_string(),
+            None if addr == self.root().addr => String::from("entry"),
+            None => format!("sub_{:03x}", addr),
+        }
+    }
+
+    // Every subroutine's calls, own and total cost summed over wherever it was called from.
+    // Recursive calls count towards the total once per level
+    pub fn subroutines(&self) -> BTreeMap<u16, (u64, Cost, Cost)> {
+        let mut subroutines: BTreeMap<u16, (u64, Cost, Cost)> = BTreeMap::new();
+
+        for (node, total) in self.nodes.iter().zip(self.totals()) {
+            let entry = subroutines.entry(node.addr).or_default();
+            entry.0 += node.calls;
+            entry.1.add(node.own);
+            entry.2.add(total);
+        }
+
+        subroutines
+    }
+
+    // Hotspots, opcode classes, subroutines and the call tree. `text` gives each instruction's
+    // disassembly
+    pub fn report<F: Fn(u16) -> String>(&self, system: &System, text: F) -> String {
+        let totals = self.totals();
+        let total = totals[0];
+        let percent = |cost: Cost| cost.cycles as f64 * 100.0 / total.cycles.max(1) as f64;
+
+        let mut report = format!(
+            "{} instructions, {} VIP cycles\n\nHotspots\n{:>10} {:>10} {:>6}  address\n",
+            total.instructions, total.cycles, "count", "cycles", "%"
+        );
+
+        let mut hot: Vec<(u16, Cost)> = (0..self.counts.len())
+            .map(|addr| (addr as u16, self.counts[addr]))
+            .filter(|(_, cost)| cost.instructions > 0)
+            .collect();
+        hot.sort_by(|a, b| b.1.cycles.cmp(&a.1.cycles).then(a.0.cmp(&b.0)));
+
+        for &(addr, cost) in hot.iter().take(HOTSPOTS) {
+            let place = match system.symbols().describe(addr) {
+                Some(name) => format!(" {}", name),
+                None => String::new(),
+            };
+
+            writeln!(
+                report,
+                "{:>10} {:>10} {:>5.1}%  {:#06x}{}  {}",
+                cost.instructions,
+                cost.cycles,
+                percent(cost),
+                addr,
+                place,
+                text(addr)
+            )
+            .ok();
+        }
+
+        writeln!(
+            report,
+            "\nOpcode classes\n{:>10} {:>10} {:>6}  class",
+            "count", "cycles", "%"
+        )
+        .ok();
+
+        for (class, cost) in CLASSES.iter().zip(self.classes.iter()) {
+            if cost.instructions > 0 {
+                writeln!(
+                    report,
+                    "{:>10} {:>10} {:>5.1}%  {}",
+                    cost.instructions,
+                    cost.cycles,
+                    percent(*cost),
+                    class
+                )
+                .ok();
+            }
+        }
+
+        writeln!(
+            report,
+            "\nSubroutines\n{:>10} {:>10} {:>10} {:>6}  subroutine",
+            "calls", "own", "total", "%"
+        )
+        .ok();
+
+        let mut subroutines: Vec<(u16, (u64, Cost, Cost))> =
+            self.subroutines().into_iter().collect();
+        subroutines.sort_by_key(|&(_, (_, _, total))| std::cmp::Reverse(total.cycles));
+
+        for (addr, (calls, own, all)) in subroutines {
+            writeln!(
+                report,
+                "{:>10} {:>10} {:>10} {:>5.1}%  {}",
+                calls,
+                own.cycles,
+                all.cycles,
+                percent(all),
+                self.name(system, addr)
+            )
+            .ok();
+        }
+
+        report.push_str("\nCall tree, total cycles\n");
+
+        // Depth first, the most expensive callee first
+        let mut stack = vec![(0, 0)];
+
+        while let Some((node, level)) = stack.pop() {
+            writeln!(
+                report,
+                "{:>10} {:>5.1}%  {}{} x{}",
+                totals[node].cycles,
+                percent(totals[node]),
+                "  ".repeat(level),
+                self.name(system, self.nodes[node].addr),
+                self.nodes[node].calls
+            )
+            .ok();
+
+            let mut children: Vec<usize> = self.nodes[node].children.values().copied().collect();
+            children.sort_by_key(|&child| std::cmp::Reverse(totals[child].cycles));
+            stack.extend(children.into_iter().rev().map(|child| (child, level + 1)));
+        }
+
+        report
+    }
+
+    // A `caller;callee cycles` line per call tree node, for flamegraph tools
+    pub fn folded(&self, system: &System) -> String {
+        let mut folded = String::new();
+
+        for (i, node) in self.nodes.iter().enumerate() {
+            if node.own.cycles == 0 {
+                continue;
+            }
+
+            let mut path = vec![self.name(system, node.addr)];
+            let mut parent = self.nodes[i].parent;
+
+            while let Some(p) = parent {
+                path.push(self.name(system, self.nodes[p].addr));
+                parent = self.nodes[p].parent;
+            }
+
+            path.reverse();
+            writeln!(folded, "{} {}", path.join(";"), node.own.cycles).ok();
+        }
+
+        folded
+    }
+}
@@ -0,0 +1,258 @@
+// The MIT License (MIT)
+
+// Copyright (c) 2021 AnonymousDapper
+
+use std::collections::BTreeSet;
+use std::fmt;
+
+use super::keymap::Keymap;
+use super::palette::Palette;
+use super::platform::{Platform, Quirks};
+use super::System;
+
+// Hand-entered entries for some of the bundled ROMs, not a full ROM database
+static DATABASE: &str = include_str!("romdb.txt");
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum RomError {
+    Empty,
+    TooLarge { size: usize, max: usize },
+    UnsupportedPlatform(Platform),
+}
+
+impl fmt::Display for RomError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Empty => write!(f, "ROM is empty"),
+            Self::TooLarge { size, max } => write!(
+                f,
+                "ROM is {} bytes, but only {} bytes fit in program memory",
+                size, max
+            ),
+            Self::UnsupportedPlatform(platform) => write!(
+                f,
+                "ROM needs the {} platform, which is unsupported",
+                platform.name()
+            ),
+        }
+    }
+}
+
+impl std::error::Error for RomError {}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct RomInfo {
+    pub title: Option<String>,
+    pub platform: Platform,
+    pub quirks: Quirks,
+    pub tickrate: Option<usize>, // Instructions per frame
+    pub keymap: Keymap,
+    pub palette: Option<Palette>,
+    pub known: bool, // Found in the database rather than guessed
+}
+
+impl RomInfo {
+    fn guess(data: &[u8]) -> Self {
+        let platform = detect_platform(data);
+
+        Self {
+            title: None,
+            platform,
+            quirks: platform.quirks(),
+            tickrate: None,
+            keymap: Keymap::default(),
+            palette: None,
+            known: false,
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Rom {
+    pub data: Vec<u8>,
+    pub sha1: String,
+    pub info: RomInfo,
+}
+
+impl Rom {
+    pub fn new(data: Vec<u8>) -> Self {
+        let sha1 = sha1_smol::Sha1::from(&data).digest().to_string();
+        let info = lookup(&sha1).unwrap_or_else(|| RomInfo::guess(&data));
+
+        Self { data, sha1, info }
+    }
+
+    // Only a platform that was picked for the ROM can be unsupported, naming one runs it anyway
+    pub fn check_platform(&self) -> Result<(), RomError> {
+        if self.info.platform.is_supported() {
+            Ok(())
+        } else {
+            Err(RomError::UnsupportedPlatform(self.info.platform))
+        }
+    }
+
+    // Loads into `system` on the platform the ROM was made for, with its quirks, unless
+    // `platform` says otherwise
+    pub fn load_into(
+        &self,
+        system: &mut System,
+        platform: Option<Platform>,
+    ) -> Result<(), RomError> {
+        match platform {
+            Some(platform) => system.set_platform(platform),
+            None => {
+                self.check_platform()?;
+                system.set_platform(self.info.platform);
+                system.set_quirks(self.info.quirks);
+            }
+        }
+
+        system.load_rom(&self.data)
+    }
+}
+
+pub fn validate(data: &[u8], max_size: usize) -> Result<(), RomError> {
+    if data.is_empty() {
+        Err(RomError::Empty)
+    } else if data.len() > max_size {
+        Err(RomError::TooLarge {
+            size: data.len(),
+            max: max_size,
+        })
+    } else {
+        Ok(())
+    }
+}
+
+pub fn lookup(sha1: &str) -> Option<RomInfo> {
+    let header = format!("[{}]", sha1.to_ascii_lowercase());
+    let mut lines = DATABASE.lines().map(str::trim);
+
+    lines.find(|line| *line == header)?;
+
+    let mut info = RomInfo {
+        title: None,
+        platform: Platform::default(),
+        quirks: Platform::default().quirks(),
+        tickrate: None,
+        keymap: Keymap::default(),
+        palette: None,
+        known: true,
+    };
+
+    let mut quirk_overrides = Vec::new();
+
+    for line in lines.take_while(|line| !line.starts_with('[')) {
+        let (key, value) = match line.split_once('=') {
+            Some((key, value)) if !line.starts_with('#') => (key.trim(), value.trim()),
+            _ => continue,
+        };
+
+        match key {
+            "title" => info.title = Some(value.to_string()),
+            "platform" => {
+                if let Some(platform) = Platform::from_name(value) {
+                    info.platform = platform;
+                }
+            }
+            "tickrate" => info.tickrate = value.parse().ok(),
+            "quirks" => quirk_overrides.extend(value.split_whitespace()),
+            "keys" => {
+                let keys = value
+                    .split_whitespace()
+                    .filter_map(|pair| {
+                        let (host, code) = pair.split_once('=')?;
+                        let host = host.chars().next()?;
+                        let code = u8::from_str_radix(code, 16).ok().filter(|c| *c < 16)?;
+
+                        Some((host, code))
+                    })
+                    .collect();
+
+                info.keymap = Keymap::new(keys);
+            }
+            "palette" => info.palette = Palette::from_name(value),
+            _ => {}
+        }
+    }
+
+    info.quirks = info.platform.quirks();
+
+    for quirk in quirk_overrides {
+        if let Some(name) = quirk.strip_prefix('+') {
+            info.quirks.set(name, true);
+        } else if let Some(name) = quirk.strip_prefix('-') {
+            info.quirks.set(name, false);
+        }
+    }
+
+    Some(info)
+}
+
+fn is_schip_op(op: u16) -> bool {
+    matches!(op, 0x00fb..=0x00ff)
+        || op & 0xfff0 == 0x00c0
+        || matches!(op & 0xf0ff, 0xf030 | 0xf075 | 0xf085)
+}
+
+fn is_xochip_op(op: u16) -> bool {
+    op == 0xf000
+        || op == 0xf002
+        || matches!(op & 0xf0ff, 0xf001 | 0xf03a)
+        || matches!(op & 0xf00f, 0x5002 | 0x5003)
+        || op & 0xfff0 == 0x00d0
+}
+
+// Only opcodes reachable from the entry point are looked at, sprite data is full of lookalikes
+pub fn detect_platform(data: &[u8]) -> Platform {
+    if data.len() > Platform::SuperChip.max_rom_size() {
+        return Platform::XoChip;
+    }
+
+    let base = Platform::default().load_address() as usize;
+    let read = |addr: usize| -> Option<u16> {
+        let offset = addr.checked_sub(base)?;
+        let bytes = data.get(offset..offset + 2)?;
+
+        Some((bytes[0] as u16) << 8 | bytes[1] as u16)
+    };
+
+    let mut seen = BTreeSet::new();
+    let mut pending = vec![base];
+    let mut platform = Platform::Chip8;
+
+    while let Some(addr) = pending.pop() {
+        if !seen.insert(addr) {
+            continue;
+        }
+
+        let op = match read(addr) {
+            Some(op) => op,
+            None => continue,
+        };
+
+        if is_xochip_op(op) {
+            return Platform::XoChip;
+        } else if is_schip_op(op) {
+            platform = Platform::SuperChip;
+        }
+
+        let next = addr + 2;
+
+        match op >> 12 {
+            0x0 if op == 0x00ee || op == 0x00fd => {}
+            0x0 if op != 0x00e0 && !is_schip_op(op) => {}
+            0x1 => pending.push((op & 0xfff) as usize),
+            0x2 => {
+                pending.push((op & 0xfff) as usize);
+                pending.push(next);
+            }
+            0x3 | 0x4 | 0x5 | 0x9 => pending.extend([next, next + 2]),
+            0xb => {}
+            0xe if matches!(op & 0xff, 0x9e | 0xa1) => pending.extend([next, next + 2]),
+            _ => pending.push(next),
+        }
+    }
+
+    platform
+}
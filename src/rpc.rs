@@ -0,0 +1,267 @@
+// The MIT License (MIT)
+
+// Copyright (c) 2021 AnonymousDapper
+
+use std::collections::HashMap;
+use std::io::{self, BufRead, Write};
+
+use serde_json::{json, Map, Value};
+
+use super::platform::Platform;
+use super::rom::Rom;
+use super::{System, DISPLAY_HEIGHT, DISPLAY_WIDTH, MAX_IPF};
+
+// JSON-RPC 2.0 error codes
+const PARSE_ERROR: i64 = -32700;
+const INVALID_REQUEST: i64 = -32600;
+const METHOD_NOT_FOUND: i64 = -32601;
+const INVALID_PARAMS: i64 = -32602;
+const FAILED: i64 = -32000; // The request was fine but couldn't be carried out
+const GUEST_ERROR: i64 = -32001; // The program faulted and the machine halted
+
+// Largest `count` for run_frames, a minute of emulated time, and for step
+const MAX_FRAMES: u64 = 60 * 60;
+const MAX_STEPS: u64 = MAX_FRAMES * MAX_IPF as u64;
+
+type RpcResult = Result<Value, (i64, String)>;
+
+fn invalid<S: Into<String>>(message: S) -> (i64, String) {
+    (INVALID_PARAMS, message.into())
+}
+
+fn param_u64(
+    params: &Map<String, Value>,
+    name: &str,
+    default: u64,
+    max: u64,
+) -> Result<u64, (i64, String)> {
+    match params.get(name) {
+        None => Ok(default),
+        Some(value) => value
+            .as_u64()
+            .filter(|value| *value <= max)
+            .ok_or_else(|| invalid(format!("`{}` must be an integer from 0 to {}", name, max))),
+    }
+}
+
+// Faults from the last run are errors, the state is still there through get_state
+fn ran(system: &System) -> RpcResult {
+    match system.error() {
+        Some(e) => Err((GUEST_ERROR, e.to_string())),
+        None => Ok(state(system)),
+    }
+}
+
+fn param_str<'a>(
+    params: &'a Map<String, Value>,
+    name: &str,
+) -> Result<Option<&'a str>, (i64, String)> {
+    match params.get(name) {
+        None => Ok(None),
+        Some(value) => value
+            .as_str()
+            .map(Some)
+            .ok_or_else(|| invalid(format!("`{}` must be a string", name))),
+    }
+}
+
+// Registers, I, PC, the stack and timers, plus whether the machine is still running
+pub fn state(system: &System) -> Value {
+    let registers: Vec<u8> = (0..16).map(|x| system.read_register(x)).collect();
+
+    json!({
+        "pc": system.pc(),
+        "i": system.ir(),
+        "v": registers,
+        "stack": system.memory().stack(),
+        "dt": system.dt(),
+        "st": system.st(),
+        "frames": system.frames(),
+        "instructions": system.instructions(),
+        "halted": system.is_halted(),
+        "waiting": system.is_waiting(),
+        "error": system.error().map(|e| e.to_string()),
+    })
+}
+
+// A pixel value per byte, row by row from the top left
+pub fn framebuffer(system: &System) -> Value {
+    json!({
+        "width": DISPLAY_WIDTH,
+        "height": DISPLAY_HEIGHT,
+        "pixels": system.display(),
+    })
+}
+
+// Answers line-delimited JSON-RPC 2.0 requests, with states saved under a name to go back to.
+// Methods are load, step, run_frames, set_keys, get_state, get_framebuffer, save_state and
+// load_state
+pub struct Server {
+    states: HashMap<String, System>,
+}
+
+impl Server {
+    #[allow(clippy::new_without_default)]
+    pub fn new() -> Self {
+        Self {
+            states: HashMap::new(),
+        }
+    }
+
+    fn call(
+        &mut self,
+        system: &mut System,
+        method: &str,
+        params: &Map<String, Value>,
+    ) -> RpcResult {
+        match method {
+            "load" => {
+                let path =
+                    param_str(params, "path")?.ok_or_else(|| invalid("`path` is missing"))?;
+                let platform = match param_str(params, "platform")? {
+                    Some(name) => Some(
+                        Platform::from_name(name)
+                            .ok_or_else(|| invalid(format!("`{}` is not a platform", name)))?,
+                    ),
+                    None => None,
+                };
+
+                let data = std::fs::read(path).map_err(|e| (FAILED, format!("{}: {}", path, e)))?;
+                Rom::new(data)
+                    .load_into(system, platform)
+                    .map_err(|e| (FAILED, format!("{}: {}", path, e)))?;
+
+                Ok(state(system))
+            }
+            "step" => {
+                for _ in 0..param_u64(params, "count", 1, MAX_STEPS)? {
+                    if system.is_halted() {
+                        break;
+                    }
+
+                    system.step();
+                }
+
+                ran(system)
+            }
+            "run_frames" => {
+                for _ in 0..param_u64(params, "count", 1, MAX_FRAMES)? {
+                    if system.is_halted() {
+                        break;
+                    }
+
+                    system.run_frame();
+                }
+
+                ran(system)
+            }
+            // Every key listed is held down, the rest are let go
+            "set_keys" => {
+                let keys = params
+                    .get("keys")
+                    .and_then(Value::as_array)
+                    .ok_or_else(|| invalid("`keys` must be a list of keys 0-15"))?;
+
+                let mut pressed = [false; 16];
+                for key in keys {
+                    match key.as_u64() {
+                        Some(key) if key < 16 => pressed[key as usize] = true,
+                        _ => return Err(invalid(format!("`{}` is not a key 0-15", key))),
+                    }
+                }
+
+                for (key, &down) in pressed.iter().enumerate() {
+                    system.set_key(key as u8, down);
+                }
+
+                Ok(state(system))
+            }
+            "get_state" => Ok(state(system)),
+            "get_framebuffer" => Ok(framebuffer(system)),
+            "save_state" => {
+                let name = param_str(params, "name")?.unwrap_or("default");
+                self.states.insert(name.to_string(), system.clone());
+
+                Ok(json!({ "name": name }))
+            }
+            "load_state" => {
+                let name = param_str(params, "name")?.unwrap_or("default");
+                *system = self
+                    .states
+                    .get(name)
+                    .ok_or_else(|| (FAILED, format!("no state saved as `{}`", name)))?
+                    .clone();
+
+                Ok(state(system))
+            }
+            _ => Err((METHOD_NOT_FOUND, format!("unknown method `{}`", method))),
+        }
+    }
+
+    // The response to one line of input, or nothing for a notification
+    pub fn handle(&mut self, system: &mut System, line: &str) -> Option<Value> {
+        let request: Value = match serde_json::from_str(line) {
+            Ok(request) => request,
+            Err(e) => return Some(error(Value::Null, PARSE_ERROR, e.to_string())),
+        };
+
+        let id = request.get("id").cloned();
+        let method = request.get("method").and_then(Value::as_str);
+        let empty = Map::new();
+
+        let params = match request.get("params") {
+            None => Ok(&empty),
+            Some(Value::Object(params)) => Ok(params),
+            Some(_) => Err(invalid("`params` must be an object")),
+        };
+
+        let result = match (method, params) {
+            (None, _) => Err((INVALID_REQUEST, String::from("`method` is missing"))),
+            (_, Err(e)) => Err(e),
+            (Some(method), Ok(params)) => self.call(system, method, params),
+        };
+
+        // Requests without an id are notifications and get no answer, unless they make no sense
+        let id = match (id, &result) {
+            (Some(id), _) => id,
+            (None, Err((INVALID_REQUEST, _))) => Value::Null,
+            (None, _) => return None,
+        };
+
+        Some(match result {
+            Ok(result) => json!({ "jsonrpc": "2.0", "id": id, "result": result }),
+            Err((code, message)) => error(id, code, message),
+        })
+    }
+
+    // Runs until `input` ends
+    pub fn serve<R: BufRead, W: Write>(
+        &mut self,
+        system: &mut System,
+        input: R,
+        mut output: W,
+    ) -> io::Result<()> {
+        for line in input.lines() {
+            let line = line?;
+
+            if line.trim().is_empty() {
+                continue;
+            }
+
+            if let Some(response) = self.handle(system, &line) {
+                writeln!(output, "{}", response)?;
+                output.flush()?;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+fn error(id: Value, code: i64, message: String) -> Value {
+    json!({
+        "jsonrpc": "2.0",
+        "id": id,
+        "error": { "code": code, "message": message },
+    })
+}
@@ -0,0 +1,395 @@
+// The MIT License (MIT)
+
+// Copyright (c) 2021 AnonymousDapper
+
+use std::fmt;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+use super::compare::Compare;
+use super::palette::Palette;
+use super::platform::Platform;
+use super::rom::Rom;
+use super::symbols::Symbols;
+use super::{System, DISPLAY_HEIGHT, DISPLAY_WIDTH};
+
+// Frames `wait` gives up after when no limit is given, ten seconds at 60Hz
+const DEFAULT_WAIT_FRAMES: u64 = 600;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ScriptError {
+    pub line: usize,
+    pub message: String,
+}
+
+impl fmt::Display for ScriptError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "line {}: {}", self.line, self.message)
+    }
+}
+
+impl std::error::Error for ScriptError {}
+
+// Something about the machine a script can look at
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Target {
+    Register(u8),
+    Index,
+    Pc,
+    Memory(String), // Address, a symbol or hex
+    Pixel(usize, usize),
+    Pixels, // How many are lit
+    Halted,
+}
+
+impl Target {
+    fn parse(words: &[&str]) -> Result<(Self, usize), String> {
+        let number = |s: &str| parse_number(s).ok_or(format!("`{}` is not a number", s));
+
+        let target = match *words {
+            ["i", ..] => (Self::Index, 1),
+            ["pc", ..] => (Self::Pc, 1),
+            ["pixels", ..] => (Self::Pixels, 1),
+            ["halted", ..] => (Self::Halted, 1),
+            ["mem", addr, ..] => (Self::Memory(addr.to_string()), 2),
+            ["pixel", x, y, ..] => (Self::Pixel(number(x)? as usize, number(y)? as usize), 3),
+            [register, ..] if register.len() == 2 && register.starts_with('v') => {
+                match u8::from_str_radix(&register[1..], 16) {
+                    Ok(x) => (Self::Register(x), 1),
+                    Err(_) => return Err(format!("`{}` is not a register", register)),
+                }
+            }
+            [other, ..] => return Err(format!("`{}` is not something that can be checked", other)),
+            [] => return Err(String::from("expected something to check")),
+        };
+
+        Ok(target)
+    }
+
+    // Addresses are symbols or hex like everywhere else, other values are decimal or 0x hex
+    fn is_address(&self) -> bool {
+        matches!(self, Self::Index | Self::Pc)
+    }
+
+    fn read(&self, system: &System) -> Result<u32, String> {
+        let value = match self {
+            Self::Register(x) => system.read_register(*x) as u32,
+            Self::Index => system.ir() as u32,
+            Self::Pc => system.pc() as u32,
+            Self::Memory(addr) => system.memory().read_u8(resolve(system, addr)?) as u32,
+            Self::Pixel(x, y) => {
+                if *x >= DISPLAY_WIDTH || *y >= DISPLAY_HEIGHT {
+                    return Err(format!("pixel {} {} is off the screen", x, y));
+                }
+                system.display()[y * DISPLAY_WIDTH + x] as u32
+            }
+            Self::Pixels => system.display().iter().filter(|&&pixel| pixel != 0).count() as u32,
+            Self::Halted => system.is_halted() as u32,
+        };
+
+        Ok(value)
+    }
+}
+
+impl fmt::Display for Target {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Register(x) => write!(f, "v{:x}", x),
+            Self::Index => write!(f, "i"),
+            Self::Pc => write!(f, "pc"),
+            Self::Memory(addr) => write!(f, "mem {}", addr),
+            Self::Pixel(x, y) => write!(f, "pixel {} {}", x, y),
+            Self::Pixels => write!(f, "pixels"),
+            Self::Halted => write!(f, "halted"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Condition {
+    target: Target,
+    compare: Compare,
+    value: String,
+}
+
+impl Condition {
+    // `target op value`, returns how many words it took
+    fn parse(words: &[&str]) -> Result<(Self, usize), String> {
+        let (target, used) = Target::parse(words)?;
+
+        match words[used..] {
+            [op, value, ..] => match Compare::from_name(op) {
+                Some(compare) => Ok((
+                    Self {
+                        target,
+                        compare,
+                        value: value.to_string(),
+                    },
+                    used + 2,
+                )),
+                None => Err(format!("`{}` is not a comparison", op)),
+            },
+            _ => Err(format!("expected a comparison after `{}`", target)),
+        }
+    }
+
+    fn value(&self, system: &System) -> Result<u32, String> {
+        if self.target.is_address() {
+            resolve(system, &self.value).map(u32::from)
+        } else {
+            parse_number(&self.value).ok_or(format!("`{}` is not a number", self.value))
+        }
+    }
+
+    // Whether it holds, and what the target actually was
+    fn check(&self, system: &System) -> Result<(bool, u32), String> {
+        let actual = self.target.read(system)?;
+        Ok((self.compare.test(actual, self.value(system)?), actual))
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Command {
+    Load(PathBuf),
+    Platform(Platform),
+    Ipf(usize),
+    Symbols(PathBuf),
+    Frames(u64),
+    Step(u64),
+    Press(u8),
+    Release(u8),
+    Tap(u8, u64),
+    Wait(Condition, u64),
+    Assert(Condition),
+    Print(Target),
+    Screenshot(PathBuf),
+}
+
+fn parse_number(s: &str) -> Option<u32> {
+    match s.strip_prefix("0x") {
+        Some(hex) => u32::from_str_radix(hex, 16).ok(),
+        None => s.parse().ok(),
+    }
+}
+
+fn resolve(system: &System, s: &str) -> Result<u16, String> {
+    system.symbols().resolve(s).ok_or(format!(
+        "`{}` is not a known symbol or a valid hex address",
+        s
+    ))
+}
+
+fn parse_key(s: &str) -> Result<u8, String> {
+    match u8::from_str_radix(s, 16) {
+        Ok(key) if key < 16 => Ok(key),
+        _ => Err(format!("`{}` is not a key, expected 0-f", s)),
+    }
+}
+
+fn parse_count(s: &str) -> Result<u64, String> {
+    parse_number(s)
+        .map(u64::from)
+        .ok_or(format!("`{}` is not a number", s))
+}
+
+impl Command {
+    fn parse(line: &str) -> Result<Self, String> {
+        let words: Vec<&str> = line.split_whitespace().collect();
+
+        let command = match words[..] {
+            ["load", path] => Self::Load(PathBuf::from(path)),
+            ["platform", name] => match Platform::from_name(name) {
+                Some(platform) => Self::Platform(platform),
+                None => return Err(format!("`{}` is not a platform", name)),
+            },
+            ["ipf", ipf] => Self::Ipf(parse_count(ipf)? as usize),
+            ["symbols", path] => Self::Symbols(PathBuf::from(path)),
+            ["frames", n] => Self::Frames(parse_count(n)?),
+            ["step"] => Self::Step(1),
+            ["step", n] => Self::Step(parse_count(n)?),
+            ["press", key] => Self::Press(parse_key(key)?),
+            ["release", key] => Self::Release(parse_key(key)?),
+            ["tap", key] => Self::Tap(parse_key(key)?, 1),
+            ["tap", key, frames] => Self::Tap(parse_key(key)?, parse_count(frames)?),
+            ["wait", ref rest @ ..] => {
+                let (condition, used) = Condition::parse(rest)?;
+
+                match rest[used..] {
+                    [] => Self::Wait(condition, DEFAULT_WAIT_FRAMES),
+                    ["within", frames] => Self::Wait(condition, parse_count(frames)?),
+                    _ => return Err(String::from("expected `within FRAMES` after the condition")),
+                }
+            }
+            ["assert", ref rest @ ..] => match Condition::parse(rest)? {
+                (condition, used) if used == rest.len() => Self::Assert(condition),
+                _ => return Err(String::from("unexpected words after the condition")),
+            },
+            ["print", ref rest @ ..] => match Target::parse(rest)? {
+                (target, used) if used == rest.len() => Self::Print(target),
+                _ => return Err(String::from("unexpected words after what to print")),
+            },
+            ["screenshot", path] => Self::Screenshot(PathBuf::from(path)),
+            [name, ..] => return Err(format!("unknown command or wrong arguments for `{}`", name)),
+            [] => unreachable!(),
+        };
+
+        Ok(command)
+    }
+}
+
+// The display as a binary PPM image, a pixel per CHIP-8 pixel
+pub fn screenshot(system: &System, palette: &Palette) -> Vec<u8> {
+    let mut image = format!("P6\n{} {}\n255\n", DISPLAY_WIDTH, DISPLAY_HEIGHT).into_bytes();
+
+    for &pixel in system.display() {
+        let (r, g, b) = palette.pixel(pixel).rgb();
+        image.extend([r, g, b]);
+    }
+
+    image
+}
+
+// A list of commands, one per line, that drive a `System` frame by frame. `#` starts a comment
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Script {
+    commands: Vec<(usize, Command)>,
+}
+
+impl Script {
+    pub fn parse(text: &str) -> Result<Self, ScriptError> {
+        let mut commands = Vec::new();
+
+        for (i, line) in text.lines().enumerate() {
+            let line = line.split('#').next().unwrap_or("").trim();
+
+            if line.is_empty() {
+                continue;
+            }
+
+            match Command::parse(line) {
+                Ok(command) => commands.push((i + 1, command)),
+                Err(message) => {
+                    return Err(ScriptError {
+                        line: i + 1,
+                        message,
+                    })
+                }
+            }
+        }
+
+        Ok(Self { commands })
+    }
+
+    pub fn len(&self) -> usize {
+        self.commands.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.commands.is_empty()
+    }
+
+    // Paths are relative to `base`, and `print` writes to `out`. Stops at the first failed
+    // assertion, timed out wait or guest error
+    pub fn run<W: Write>(
+        &self,
+        system: &mut System,
+        base: &Path,
+        out: &mut W,
+    ) -> Result<(), ScriptError> {
+        let mut platform = None;
+
+        for (line, command) in self.commands.iter() {
+            let error = |message: String| ScriptError {
+                line: *line,
+                message,
+            };
+
+            match command {
+                Command::Load(path) => {
+                    let data = std::fs::read(base.join(path))
+                        .map_err(|e| error(format!("{}: {}", path.display(), e)))?;
+
+                    Rom::new(data)
+                        .load_into(system, platform)
+                        .map_err(|e| error(format!("{}: {}", path.display(), e)))?;
+                }
+                Command::Platform(p) => platform = Some(*p),
+                Command::Ipf(ipf) => system.set_ipf(*ipf),
+                Command::Symbols(path) => {
+                    let text = std::fs::read_to_string(base.join(path))
+                        .map_err(|e| error(format!("{}: {}", path.display(), e)))?;
+                    let symbols = Symbols::parse(&text)
+                        .map_err(|e| error(format!("{}: {}", path.display(), e)))?;
+
+                    system.set_symbols(symbols);
+                }
+                Command::Frames(n) => {
+                    for _ in 0..*n {
+                        system.run_frame();
+                    }
+                }
+                Command::Step(n) => {
+                    for _ in 0..*n {
+                        system.step();
+                    }
+                }
+                Command::Press(key) => system.set_key(*key, true),
+                Command::Release(key) => system.set_key(*key, false),
+                Command::Tap(key, frames) => {
+                    system.set_key(*key, true);
+                    for _ in 0..*frames {
+                        system.run_frame();
+                    }
+                    system.set_key(*key, false);
+                }
+                Command::Wait(condition, limit) => {
+                    let mut frames = 0;
+
+                    // A guest error ends the wait and is reported below
+                    while !condition.check(system).map_err(error)?.0 {
+                        if frames == *limit || system.is_halted() {
+                            return Err(error(format!(
+                                "`{}` still {} after {} frames",
+                                condition.target,
+                                condition.target.read(system).map_err(error)?,
+                                frames
+                            )));
+                        }
+
+                        system.run_frame();
+                        frames += 1;
+
+                        if system.error().is_some() {
+                            break;
+                        }
+                    }
+                }
+                Command::Assert(condition) => {
+                    let (holds, actual) = condition.check(system).map_err(error)?;
+
+                    if !holds {
+                        return Err(error(format!(
+                            "assertion failed, `{}` is {} ({:#x})",
+                            condition.target, actual, actual
+                        )));
+                    }
+                }
+                Command::Print(target) => {
+                    let value = target.read(system).map_err(error)?;
+                    writeln!(out, "{} = {} ({:#x})", target, value, value)
+                        .map_err(|e| error(e.to_string()))?;
+                }
+                Command::Screenshot(path) => {
+                    std::fs::write(base.join(path), screenshot(system, &Palette::default()))
+                        .map_err(|e| error(format!("{}: {}", path.display(), e)))?;
+                }
+            }
+
+            if let Some(e) = system.error() {
+                return Err(error(system.symbols().annotate(e, e.pc())));
+            }
+        }
+
+        Ok(())
+    }
+}
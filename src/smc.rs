@@ -0,0 +1,137 @@
+// The MIT License (MIT)
+
+// Copyright (c) 2021 AnonymousDapper
+
+use std::collections::BTreeMap;
+use std::fmt::Write;
+
+use super::System;
+
+// A store that changed code, either code that had already run or code that runs later on
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Patch {
+    pub writer: u16,      // PC of the store
+    pub addr: u16,        // Byte written
+    pub instruction: u16, // Start of the instruction the byte belongs to
+    pub ran_before: bool, // The instruction had run before it was written
+    pub count: u64,       // Stores that made this patch
+}
+
+// Watches program stores for ones landing in code
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SmcDetector {
+    executed: Vec<bool>,
+    starts: Vec<bool>,         // Where executed instructions began
+    written: Vec<Option<u16>>, // Store that wrote a byte not yet executed
+    patches: BTreeMap<(u16, u16, bool), Patch>,
+}
+
+impl SmcDetector {
+    pub fn new(size: usize) -> Self {
+        Self {
+            executed: vec![false; size],
+            starts: vec![false; size],
+            written: vec![None; size],
+            patches: BTreeMap::new(),
+        }
+    }
+
+    fn record(&mut self, writer: u16, addr: u16, instruction: u16, ran_before: bool) {
+        self.patches
+            .entry((writer, addr, ran_before))
+            .or_insert(Patch {
+                writer,
+                addr,
+                instruction,
+                ran_before,
+                count: 0,
+            })
+            .count += 1;
+    }
+
+    pub fn mark_executed(&mut self, pc: u16) {
+        let len = self.executed.len();
+        self.starts[pc as usize % len] = true;
+
+        for addr in [pc, pc.wrapping_add(1)] {
+            let i = addr as usize % len;
+            self.executed[i] = true;
+
+            if let Some(writer) = self.written[i].take() {
+                self.record(writer, i as u16, pc, false);
+            }
+        }
+    }
+
+    pub fn mark_written(&mut self, addr: u16, writer: u16) {
+        let len = self.executed.len();
+        let i = addr as usize % len;
+
+        if self.executed[i] {
+            let instruction = if self.starts[i] {
+                i
+            } else {
+                (i + len - 1) % len
+            };
+            self.record(writer, i as u16, instruction as u16, true);
+        } else {
+            self.written[i] = Some(writer);
+        }
+    }
+
+    pub fn patches(&self) -> impl Iterator<Item = &Patch> {
+        self.patches.values()
+    }
+
+    pub fn len(&self) -> usize {
+        self.patches.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.patches.is_empty()
+    }
+
+    // Whether the instruction at `addr` has been patched
+    pub fn is_patched(&self, addr: u16) -> bool {
+        self.patches.values().any(|patch| patch.instruction == addr)
+    }
+
+    // A line per store and patched byte. Each patched instruction is decoded again from memory
+    // by `text`, next to what the ROM had there
+    pub fn report<F: Fn(u16) -> String>(&self, system: &System, text: F) -> String {
+        if self.patches.is_empty() {
+            return String::from("no self-modifying code found\n");
+        }
+
+        let rom = system.load_address as usize..system.load_address as usize + system.rom.len();
+        let mut report = String::new();
+
+        for patch in self.patches.values() {
+            let original = if rom.contains(&(patch.instruction as usize)) {
+                let i = patch.instruction as usize - rom.start;
+                let bytes = [system.rom[i], system.rom.get(i + 1).copied().unwrap_or(0)];
+                format!("{:#06x}", u16::from_be_bytes(bytes))
+            } else {
+                String::from("------")
+            };
+
+            writeln!(
+                report,
+                "{} wrote {:#06x} {} it ran, patched {}x | {:#06x} ({} -> {:#06x}) {}",
+                system
+                    .symbols()
+                    .annotate(format!("{:#06x}", patch.writer), patch.writer),
+                patch.addr,
+                if patch.ran_before { "after" } else { "before" },
+                patch.count,
+                patch.instruction,
+                original,
+                system.mem.read_u16(patch.instruction),
+                text(patch.instruction)
+            )
+            .ok();
+        }
+
+        report
+    }
+}
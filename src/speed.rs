@@ -0,0 +1,144 @@
+// The MIT License (MIT)
+
+// Copyright (c) 2021 AnonymousDapper
+
+use std::time::{Duration, Instant};
+
+use super::timing::Timing;
+use super::System;
+
+// Emulated frames run per host frame while fast-forwarding
+pub const FAST_FORWARD_FRAMES: usize = 8;
+
+// Speed hotkeys step through these instructions-per-frame settings
+const IPF_STEPS: [usize; 16] = [
+    1, 2, 4, 7, 10, 15, 20, 30, 50, 100, 200, 500, 1000, 2000, 5000, 10000,
+];
+
+const SAMPLE_TIME: Duration = Duration::from_secs(1);
+
+#[derive(Debug)]
+pub struct Speed {
+    pub paused: bool,
+    pub fast_forward: bool,
+    sample_start: Instant,
+    sample_instructions: u64,
+    sample_frames: u64,
+    ips: u64,
+    fps: u64,
+}
+
+impl Speed {
+    #[allow(clippy::new_without_default)]
+    pub fn new() -> Self {
+        Self {
+            paused: false,
+            fast_forward: false,
+            sample_start: Instant::now(),
+            sample_instructions: 0,
+            sample_frames: 0,
+            ips: 0,
+            fps: 0,
+        }
+    }
+
+    // Emulated frames to run before the next host frame
+    pub fn frames(&self) -> usize {
+        if self.paused {
+            0
+        } else if self.fast_forward {
+            FAST_FORWARD_FRAMES
+        } else {
+            1
+        }
+    }
+
+    // Carrying on from a breakpoint runs the instruction it stopped on
+    pub fn toggle_pause(&mut self, system: &mut System) {
+        self.paused = !self.paused;
+
+        if !self.paused {
+            system.resume();
+        }
+    }
+
+    // Call after running frames, so a breakpoint pauses the frontend too
+    pub fn check_break(&mut self, system: &System) {
+        if system.break_hit().is_some() {
+            self.paused = true;
+        }
+    }
+
+    // VIP timing runs at the VIP's own clock, so these do nothing there
+    pub fn faster(&self, system: &mut System) {
+        if system.timing() == Timing::Vip {
+            return;
+        }
+
+        let ipf = system.ipf();
+
+        if let Some(&step) = IPF_STEPS.iter().find(|&&step| step > ipf) {
+            system.set_ipf(step);
+        }
+    }
+
+    pub fn slower(&self, system: &mut System) {
+        if system.timing() == Timing::Vip {
+            return;
+        }
+
+        let ipf = system.ipf();
+
+        if let Some(&step) = IPF_STEPS.iter().rev().find(|&&step| step < ipf) {
+            system.set_ipf(step);
+        }
+    }
+
+    // Call once per host frame, the rates are refreshed about once a second
+    pub fn sample(&mut self, system: &System) {
+        let elapsed = self.sample_start.elapsed();
+
+        if elapsed >= SAMPLE_TIME {
+            let secs = elapsed.as_secs_f64();
+
+            self.ips = ((system.instructions() - self.sample_instructions) as f64 / secs) as u64;
+            self.fps = ((system.frames() - self.sample_frames) as f64 / secs).round() as u64;
+
+            self.sample_start = Instant::now();
+            self.sample_instructions = system.instructions();
+            self.sample_frames = system.frames();
+        }
+    }
+
+    pub fn ips(&self) -> u64 {
+        self.ips
+    }
+
+    pub fn fps(&self) -> u64 {
+        self.fps
+    }
+
+    pub fn status(&self, system: &System) -> String {
+        let state = if system.is_halted() {
+            String::from(" [halted]")
+        } else if let Some(addr) = system.break_hit() {
+            match system.symbols().name(addr) {
+                Some(name) => format!(" [break {}]", name),
+                None => format!(" [break {:#06x}]", addr),
+            }
+        } else if self.paused {
+            String::from(" [paused]")
+        } else if self.fast_forward {
+            String::from(" [fast-forward]")
+        } else {
+            String::new()
+        };
+
+        let rate = match system.timing() {
+            Timing::Ipf => format!("{} IPF", system.ipf()),
+            Timing::Vip => String::from("VIP, speed n/a"),
+        };
+
+        format!("{} | {} IPS | {} FPS{}", rate, self.ips, self.fps, state)
+    }
+}
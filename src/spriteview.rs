@@ -0,0 +1,180 @@
+// The MIT License (MIT)
+
+// Copyright (c) 2021 AnonymousDapper
+
+use std::io::Write;
+
+use super::layout::Rect;
+use super::platform::Platform;
+use super::System;
+
+const ON: char = '█';
+const OFF: char = '.';
+
+// Gap between sprite sheet columns while browsing
+const COLUMN_GAP: usize = 2;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Sprite {
+    pub addr: u16,
+    pub rows: usize,
+    pub wide: bool, // SCHIP's 16x16, two bytes per row
+}
+
+// The sprite the instruction at PC is about to draw
+pub fn pending_sprite(system: &System) -> Option<Sprite> {
+    let (_, decoded, _) = system.read_decode();
+    let schip = matches!(system.platform, Platform::SuperChip | Platform::XoChip);
+
+    match decoded {
+        (0xd, _, _, 0) if schip => Some(Sprite {
+            addr: system.ir,
+            rows: 16,
+            wide: true,
+        }),
+        (0xd, _, _, n) => Some(Sprite {
+            addr: system.ir,
+            rows: n as usize,
+            wide: false,
+        }),
+        _ => None,
+    }
+}
+
+// Shows the sprite at I as DRW sees it, or every byte from an address as a sprite sheet
+#[derive(Debug)]
+pub struct SpriteView {
+    last: Option<Sprite>,
+    browse: Option<u16>,
+}
+
+impl SpriteView {
+    #[allow(clippy::new_without_default)]
+    pub fn new() -> Self {
+        Self {
+            last: None,
+            browse: None,
+        }
+    }
+
+    // Remembers the last sprite drawn, call before each instruction runs
+    pub fn trace(&mut self, system: &System) {
+        if let Some(sprite) = pending_sprite(system) {
+            self.last = Some(sprite);
+        }
+    }
+
+    pub fn is_browsing(&self) -> bool {
+        self.browse.is_some()
+    }
+
+    // Browsing starts at the program
+    pub fn toggle_browse(&mut self, system: &System) {
+        self.browse = match self.browse {
+            Some(_) => None,
+            None => Some(system.load_address),
+        };
+    }
+
+    pub fn scroll(&mut self, system: &System, bytes: i32) {
+        if let Some(addr) = self.browse {
+            let size = system.mem.ram.len() as i32;
+            self.browse = Some((addr as i32 + bytes).rem_euclid(size) as u16);
+        }
+    }
+
+    fn bitmap(byte: u8) -> String {
+        (0..8)
+            .map(|bit| if byte & (0x80 >> bit) != 0 { ON } else { OFF })
+            .collect()
+    }
+
+    pub fn print(&self, system: &System, pane: Rect, out: &mut dyn Write) {
+        let mut buffer = format!(
+            "\x1b[{};{}H\x1b[32;40;4;1m< Sprite >\x1b[0m",
+            pane.row, pane.col
+        );
+
+        let rows = pane.height.saturating_sub(2);
+        let mut lines = vec![String::new(); rows];
+
+        let status = match self.browse {
+            // Columns of 8 pixel wide strips, each carrying on from the last
+            Some(addr) => {
+                let columns = ((pane.width + COLUMN_GAP) / (8 + COLUMN_GAP)).max(1);
+
+                for column in 0..columns {
+                    for (row, line) in lines.iter_mut().enumerate() {
+                        let offset = (column * rows + row) as u16;
+
+                        if column > 0 {
+                            line.push_str(&" ".repeat(COLUMN_GAP));
+                        }
+                        line.push_str(&Self::bitmap(system.mem.read_u8(addr.wrapping_add(offset))));
+                    }
+                }
+
+                let end = addr.wrapping_add((columns * rows) as u16);
+                format!("\x1b[91mBROWSE\x1b[0m {:#06x}-{:#06x}", addr, end)
+            }
+            None => match pending_sprite(system).or(self.last) {
+                Some(sprite) => {
+                    let width = if sprite.wide { 2 } else { 1 };
+
+                    for (row, line) in lines.iter_mut().enumerate().take(sprite.rows) {
+                        let addr = sprite.addr.wrapping_add((row * width) as u16);
+                        let bytes: Vec<u8> = (0..width)
+                            .map(|i| system.mem.read_u8(addr.wrapping_add(i as u16)))
+                            .collect();
+
+                        line.push_str("\x1b[90m");
+                        for byte in bytes.iter() {
+                            line.push_str(&format!("{:02x}", byte));
+                        }
+                        line.push_str("\x1b[0m ");
+
+                        for byte in bytes {
+                            line.push_str(&Self::bitmap(byte));
+                        }
+                    }
+
+                    format!(
+                        "\x1b[33mI\x1b[0m {:#06x} {}x{}",
+                        sprite.addr,
+                        width * 8,
+                        sprite.rows
+                    )
+                }
+                None => String::from("no sprite drawn"),
+            },
+        };
+
+        buffer.push_str(&format!(
+            "\x1b[{};{}H{:width$}\x1b[{};{}H{}",
+            pane.row + 1,
+            pane.col,
+            "",
+            pane.row + 1,
+            pane.col,
+            status,
+            width = pane.width
+        ));
+
+        for (i, line) in lines.iter().enumerate() {
+            let row = pane.row + 2 + i;
+
+            buffer.push_str(&format!(
+                "\x1b[{};{}H{:width$}\x1b[{};{}H{}",
+                row,
+                pane.col,
+                "",
+                row,
+                pane.col,
+                line,
+                width = pane.width
+            ));
+        }
+
+        write!(out, "{}", buffer).ok();
+    }
+}
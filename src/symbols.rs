@@ -0,0 +1,123 @@
+// The MIT License (MIT)
+
+// Copyright (c) 2021 AnonymousDapper
+
+use std::collections::{BTreeMap, HashMap};
+use std::fmt;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SymbolError {
+    pub line: usize,
+    pub text: String,
+}
+
+impl fmt::Display for SymbolError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "line {}: expected `address name` or `name = address`, found `{}`",
+            self.line, self.text
+        )
+    }
+}
+
+impl std::error::Error for SymbolError {}
+
+fn parse_addr(s: &str) -> Option<u16> {
+    u16::from_str_radix(s.trim_start_matches("0x"), 16).ok()
+}
+
+// Names for addresses, from an assembler's label output
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Symbols {
+    names: BTreeMap<u16, String>,
+    addrs: HashMap<String, u16>,
+}
+
+impl Symbols {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    // One symbol per line, either a plain `0x202 main` list or Octo's `main = 0x202`.
+    // Blank lines and anything after `#` or `;` are skipped
+    pub fn parse(text: &str) -> Result<Self, SymbolError> {
+        let mut symbols = Self::new();
+
+        for (i, line) in text.lines().enumerate() {
+            let line = line.split(['#', ';']).next().unwrap_or("").trim();
+
+            if line.is_empty() {
+                continue;
+            }
+
+            let fields: Vec<&str> = line.split_whitespace().collect();
+
+            let symbol = match fields[..] {
+                [name, "=", addr] => parse_addr(addr).map(|addr| (addr, name)),
+                [addr, name] => parse_addr(addr).map(|addr| (addr, name)),
+                _ => None,
+            };
+
+            match symbol {
+                Some((addr, name)) => symbols.insert(addr, name),
+                None => {
+                    return Err(SymbolError {
+                        line: i + 1,
+                        text: line.to_string(),
+                    })
+                }
+            }
+        }
+
+        Ok(symbols)
+    }
+
+    // Later names for the same address replace earlier ones
+    pub fn insert(&mut self, addr: u16, name: &str) {
+        if let Some(old) = self.names.insert(addr, name.to_string()) {
+            self.addrs.remove(&old);
+        }
+        self.addrs.insert(name.to_string(), addr);
+    }
+
+    pub fn len(&self) -> usize {
+        self.names.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.names.is_empty()
+    }
+
+    pub fn name(&self, addr: u16) -> Option<&str> {
+        self.names.get(&addr).map(String::as_str)
+    }
+
+    pub fn addr(&self, name: &str) -> Option<u16> {
+        self.addrs.get(name).copied()
+    }
+
+    // A symbol name, or failing that a hex address. Names win, since plenty of them are valid hex
+    pub fn resolve(&self, s: &str) -> Option<u16> {
+        self.addr(s).or_else(|| parse_addr(s))
+    }
+
+    // `text` followed by where `addr` is, for messages about the code at `addr`
+    pub fn annotate<T: fmt::Display>(&self, text: T, addr: u16) -> String {
+        match self.describe(addr) {
+            Some(name) => format!("{} in {}", text, name),
+            None => text.to_string(),
+        }
+    }
+
+    // The nearest symbol at or before `addr`, with the offset from it
+    pub fn describe(&self, addr: u16) -> Option<String> {
+        self.names
+            .range(..=addr)
+            .next_back()
+            .map(|(&start, name)| match addr - start {
+                0 => name.clone(),
+                offset => format!("{}+{:#x}", name, offset),
+            })
+    }
+}
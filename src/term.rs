@@ -0,0 +1,209 @@
+// The MIT License (MIT)
+
+// Copyright (c) 2021 AnonymousDapper
+
+use std::fs::File;
+use std::io::{stdout, Read, Write};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use termion::raw::IntoRawMode;
+
+use super::dis::Disassembler;
+use super::display::Screen;
+use super::keymap::Keymap;
+use super::memmap::Policy;
+use super::speed::Speed;
+use super::{System, TIMER_HZ};
+
+// A tty only reports key presses, so each press holds the key down for a little while
+const KEY_HOLD: Duration = Duration::from_millis(100);
+
+// Reads keys from stdin in raw mode
+pub fn run(
+    system: &mut System,
+    screen: Screen,
+    maybe_dis: Option<Disassembler>,
+    keymap: &Keymap,
+    memory_log: Option<File>,
+) {
+    // Raw mode swallows SIGINT, so CTRL+C is picked up from the input instead
+    let raw = stdout().into_raw_mode().ok();
+
+    run_with_input(
+        system,
+        screen,
+        maybe_dis,
+        keymap,
+        memory_log,
+        termion::async_stdin(),
+    );
+
+    std::mem::drop(raw);
+}
+
+// Runs until the system halts, taking keys from `input`, which must not block, and drawing to
+// the screen's output
+pub fn run_with_input<R: Read>(
+    system: &mut System,
+    mut screen: Screen,
+    mut maybe_dis: Option<Disassembler>,
+    keymap: &Keymap,
+    mut memory_log: Option<File>,
+    mut input: R,
+) {
+    let mut bytes = [0; 32];
+    let mut held: [Option<Instant>; 16] = [None; 16];
+
+    let frame_period = Duration::from_secs(1) / TIMER_HZ;
+    let mut next_frame = Instant::now();
+    let mut speed = Speed::new();
+    let mut status = String::new();
+    let mut warning = None;
+
+    if let Some(ref mut dis) = maybe_dis {
+        dis.scan_labels(system);
+    }
+
+    screen.init();
+    screen.write_display(system.display());
+
+    while !system.is_halted() {
+        if screen.take_interrupt() {
+            system.halt();
+        }
+
+        let count = input.read(&mut bytes).unwrap_or(0);
+        let mut keys = &bytes[..count];
+
+        // While editing memory every key goes to the memory pane, apart from CTRL+C
+        if let Some(ref mut dis) = maybe_dis {
+            if dis.memory_mut().is_editing() {
+                if keys.contains(&0x3) {
+                    system.halt();
+                }
+
+                dis.memory_mut().input(system, keys);
+                keys = &[];
+            }
+        }
+
+        // A tty can't report held keys, so fast-forward toggles like pause
+        for &byte in keys.iter() {
+            match byte {
+                0x3 => system.halt(),
+                b'p' => speed.toggle_pause(system),
+                b'm' if speed.paused => {
+                    if let Some(ref mut dis) = maybe_dis {
+                        dis.memory_mut().set_editing(system, true);
+                    }
+                }
+                // The disassembly scrolls on its own, with breakpoints set on the selected line
+                b'[' | b']' | b'.' | b'b' if maybe_dis.is_some() => {
+                    if let Some(ref mut dis) = maybe_dis {
+                        match byte {
+                            b'[' => dis.scroll(system, -1),
+                            b']' => dis.scroll(system, 1),
+                            b'.' => dis.follow_pc(),
+                            _ => dis.toggle_breakpoint(system),
+                        }
+                    }
+                }
+                // Browsing memory as a sprite sheet, a byte or a whole column at a time
+                b'g' | b'<' | b'>' | b'{' | b'}' if maybe_dis.is_some() => {
+                    if let Some(ref mut dis) = maybe_dis {
+                        let rows = screen.layout().sprite.map_or(1, |pane| pane.height - 2);
+                        let sprite = dis.sprite_mut();
+
+                        match byte {
+                            b'g' => sprite.toggle_browse(system),
+                            b'<' => sprite.scroll(system, -1),
+                            b'>' => sprite.scroll(system, 1),
+                            b'{' => sprite.scroll(system, -(rows as i32)),
+                            _ => sprite.scroll(system, rows as i32),
+                        }
+                    }
+                }
+                b'\t' => speed.fast_forward = !speed.fast_forward,
+                b'=' | b'+' => speed.faster(system),
+                b'-' => speed.slower(system),
+                _ => {
+                    if let Some(key) = keymap.translate(byte as char) {
+                        system.set_key(key, true);
+                        held[key as usize] = Some(Instant::now());
+                    }
+                }
+            }
+        }
+
+        for (key, pressed) in held.iter_mut().enumerate() {
+            if pressed.is_some_and(|t| t.elapsed() >= KEY_HOLD) {
+                system.set_key(key as u8, false);
+                *pressed = None;
+            }
+        }
+
+        let resized = screen.check_resize();
+
+        for _ in 0..speed.frames() {
+            match maybe_dis {
+                Some(ref mut dis) => system.run_frame_with(|system| dis.trace(system)),
+                None => system.run_frame(),
+            }
+        }
+        speed.check_break(system);
+
+        if system.take_redraw() || resized {
+            screen.set_colour_map(system.colour_map());
+            screen.write_display(system.display());
+        } else {
+            screen.update(system.display());
+        }
+
+        if let Some(ref mut dis) = maybe_dis {
+            let layout = screen.layout().clone();
+            let out = screen.output();
+
+            dis.print_state(system, &layout, out);
+            dis.print_dis(system, &layout, out);
+            dis.print_memory(system, &layout, out);
+            dis.print_sprite(system, &layout, out);
+        }
+
+        for event in system.take_memory_events() {
+            if let Some(ref mut log) = memory_log {
+                writeln!(log, "{}", system.symbols().annotate(event, event.pc)).ok();
+            }
+
+            if event.policy == Policy::Warn {
+                warning = Some(event);
+            }
+        }
+
+        speed.sample(system);
+
+        let new_status = match warning {
+            Some(ref event) => format!("{} | \x1b[93m{}\x1b[0m", speed.status(system), event),
+            None => speed.status(system),
+        };
+
+        if new_status != status || resized {
+            screen.write_status(&new_status);
+            status = new_status;
+        }
+
+        // Nothing above ends in a newline, so stdout would otherwise hold on to the last frame
+        screen.output().flush().ok();
+
+        // Frames are paced against a fixed schedule so the timers run at a true 60Hz,
+        // after a long stall the schedule restarts rather than rushing to catch up
+        next_frame += frame_period;
+        let now = Instant::now();
+
+        if next_frame > now {
+            thread::sleep(next_frame - now);
+        } else if now - next_frame > frame_period * 4 {
+            next_frame = now;
+        }
+    }
+}
@@ -0,0 +1,76 @@
+// The MIT License (MIT)
+
+// Copyright (c) 2021 AnonymousDapper
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Timing {
+    #[default]
+    Ipf, // A fixed number of instructions per frame
+    Vip, // Each instruction costs its COSMAC VIP machine cycles
+}
+
+impl Timing {
+    pub const NAMES: &'static [&'static str] = &["ipf", "vip"];
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "ipf" => Some(Self::Ipf),
+            "vip" => Some(Self::Vip),
+            _ => None,
+        }
+    }
+}
+
+// The 1802 runs at 1.76MHz with 8 clocks per machine cycle, which is 3668 machine cycles a frame.
+// Display DMA steals one cycle per byte shown (128 lines of 8 bytes) and the interrupt routine
+// that runs the timers takes a few more, the interpreter gets whatever is left
+const VIP_CYCLES_PER_FRAME: u32 = 3668;
+const VIP_DMA_CYCLES: u32 = 128 * 8;
+const VIP_INTERRUPT_CYCLES: u32 = 48;
+
+pub const VIP_FRAME_CYCLES: u32 = VIP_CYCLES_PER_FRAME - VIP_DMA_CYCLES - VIP_INTERRUPT_CYCLES;
+
+// Fetching the opcode and dispatching through the interpreter's jump table
+const VIP_FETCH_CYCLES: u32 = 40;
+
+// Machine cycles the VIP interpreter spends on `op`, `vx` is the value of Vx before it ran
+pub fn vip_cycles(op: u16, vx: u8, skipped: bool) -> u32 {
+    let x = (op >> 8 & 0xf) as u32;
+    let skip = if skipped { 4 } else { 0 };
+
+    let exec = match op >> 12 {
+        0x0 if op == 0x00e0 => 24 + 256 * 3, // Three cycles per cleared byte
+        0x0 => 10,
+        0x1 => 12,
+        0x2 => 26,
+        0x3 | 0x4 => 10 + skip,
+        0x5 | 0x9 => 14 + skip,
+        0x6 => 6,
+        0x7 => 10,
+        0x8 => 44,
+        0xa => 12,
+        0xb => 22,
+        0xc => 36,
+        0xd => draw_cycles(vx, (op & 0xf) as u32),
+        0xe => 14 + skip,
+        0xf => match op & 0xff {
+            0x1e => 16,
+            0x29 => 20,
+            0x33 => 88 + 3 * 16, // One division loop per digit
+            0x55 | 0x65 => 14 + 14 * (x + 1),
+            _ => 10,
+        },
+        _ => unreachable!(),
+    };
+
+    VIP_FETCH_CYCLES + exec
+}
+
+// Sprites that don't start on a byte boundary are shifted into two bytes a row, one bit at a time
+fn draw_cycles(x: u8, height: u32) -> u32 {
+    let shift = (x % 8) as u32;
+
+    let row = if shift == 0 { 30 } else { 34 + 4 * shift };
+
+    26 + height * row
+}
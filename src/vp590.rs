@@ -0,0 +1,86 @@
+// The MIT License (MIT)
+
+// Copyright (c) 2021 AnonymousDapper
+
+use super::{DISPLAY_HEIGHT, DISPLAY_WIDTH};
+
+// The VP-590 colour board used by CHIP-8X, indexed by the low 3 bits of a colour value
+pub const COLOURS: [(u8, u8, u8); 8] = [
+    (0x00, 0x00, 0x00), // Black
+    (0xff, 0x00, 0x00), // Red
+    (0x00, 0x00, 0xff), // Blue
+    (0xff, 0x00, 0xff), // Violet
+    (0x00, 0xff, 0x00), // Green
+    (0xff, 0xff, 0x00), // Yellow
+    (0x00, 0xff, 0xff), // Aqua
+    (0xff, 0xff, 0xff), // White
+];
+
+// 02A0 steps the background through these
+const BACKGROUNDS: [u8; 4] = [2, 0, 4, 1];
+
+// Foreground colour is set per 8 pixel wide column for each pixel row
+const ZONE_WIDTH: usize = 8;
+const ZONE_HEIGHT: usize = 4;
+const ZONE_COLUMNS: usize = DISPLAY_WIDTH / ZONE_WIDTH;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ColourMap {
+    background: usize,
+    zones: [u8; ZONE_COLUMNS * DISPLAY_HEIGHT],
+}
+
+impl ColourMap {
+    #[allow(clippy::new_without_default)]
+    pub fn new() -> Self {
+        Self {
+            background: 0,
+            zones: [1; ZONE_COLUMNS * DISPLAY_HEIGHT],
+        }
+    }
+
+    pub fn step_background(&mut self) {
+        self.background = (self.background + 1) % BACKGROUNDS.len();
+    }
+
+    // BxyN: the low nibble of `x` is the first column of 8 pixels and the high nibble how many more
+    // to colour. With `rows` 0, `y` works the same way in rows of 4 pixels, otherwise it is the first
+    // pixel row and `rows` the number of rows
+    pub fn set_zone(&mut self, x: u8, y: u8, rows: u8, colour: u8) {
+        let first = (x & 0xf) as usize;
+        let columns = first..=(first + (x >> 4) as usize).min(ZONE_COLUMNS - 1);
+
+        let lines = if rows == 0 {
+            let top = (y & 0xf) as usize * ZONE_HEIGHT;
+            top..top + ((y >> 4) as usize + 1) * ZONE_HEIGHT
+        } else {
+            let top = y as usize % DISPLAY_HEIGHT;
+            top..top + rows as usize
+        };
+
+        for line in lines.filter(|&line| line < DISPLAY_HEIGHT) {
+            for column in columns.clone().filter(|&column| column < ZONE_COLUMNS) {
+                self.zones[column + line * ZONE_COLUMNS] = colour & 0x7;
+            }
+        }
+    }
+
+    pub fn background(&self) -> (u8, u8, u8) {
+        COLOURS[BACKGROUNDS[self.background] as usize]
+    }
+
+    pub fn foreground(&self, x: usize, y: usize) -> (u8, u8, u8) {
+        let zone =
+            (x / ZONE_WIDTH).min(ZONE_COLUMNS - 1) + y.min(DISPLAY_HEIGHT - 1) * ZONE_COLUMNS;
+
+        COLOURS[self.zones[zone] as usize]
+    }
+
+    pub fn colour(&self, x: usize, y: usize, on: bool) -> (u8, u8, u8) {
+        if on {
+            self.foreground(x, y)
+        } else {
+            self.background()
+        }
+    }
+}
@@ -0,0 +1,82 @@
+// The MIT License (MIT)
+
+// Copyright (c) 2021 AnonymousDapper
+
+use wasm_bindgen::prelude::*;
+
+use super::timing::Timing;
+use super::{System, DEFAULT_IPF, DISPLAY_HEIGHT, DISPLAY_WIDTH};
+
+// JS drives this from requestAnimationFrame, one `run_frame` per 60Hz tick
+#[wasm_bindgen]
+#[derive(Debug)]
+pub struct Emulator {
+    system: System,
+}
+
+#[wasm_bindgen]
+impl Emulator {
+    #[wasm_bindgen(constructor)]
+    #[allow(clippy::new_without_default)]
+    pub fn new() -> Self {
+        Self {
+            system: System::new(DEFAULT_IPF),
+        }
+    }
+
+    pub fn load_rom(&mut self, data: &[u8]) -> Result<(), String> {
+        self.system.load_rom(data).map_err(|e| e.to_string())
+    }
+
+    pub fn reset(&mut self) {
+        self.system.restart();
+    }
+
+    pub fn run_frame(&mut self) {
+        self.system.run_frame();
+    }
+
+    pub fn ipf(&self) -> usize {
+        self.system.ipf()
+    }
+
+    pub fn set_ipf(&mut self, ipf: usize) {
+        self.system.set_ipf(ipf);
+    }
+
+    // Charge instructions their COSMAC VIP cycle cost instead of running `ipf` a frame
+    pub fn set_vip_timing(&mut self, enabled: bool) {
+        self.system
+            .set_timing(if enabled { Timing::Vip } else { Timing::Ipf });
+    }
+
+    pub fn set_key(&mut self, key: u8, pressed: bool) {
+        self.system.set_key(key, pressed);
+    }
+
+    pub fn width(&self) -> usize {
+        DISPLAY_WIDTH
+    }
+
+    pub fn height(&self) -> usize {
+        DISPLAY_HEIGHT
+    }
+
+    // One byte per pixel, row major
+    pub fn framebuffer(&self) -> Vec<u8> {
+        self.system.display().to_vec()
+    }
+
+    // True when the display changed since the last call
+    pub fn take_redraw(&mut self) -> bool {
+        self.system.take_redraw()
+    }
+
+    pub fn sound_active(&self) -> bool {
+        self.system.sound_active()
+    }
+
+    pub fn halted(&self) -> bool {
+        self.system.is_halted()
+    }
+}
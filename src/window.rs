@@ -0,0 +1,149 @@
+// The MIT License (MIT)
+
+// Copyright (c) 2021 AnonymousDapper
+
+use std::fs::File;
+use std::io::Write;
+
+use minifb::{Key, KeyRepeat, Window, WindowOptions};
+
+use super::keymap::Keymap;
+use super::memmap::Policy;
+use super::palette::Palette;
+use super::speed::Speed;
+use super::{System, DISPLAY_HEIGHT, DISPLAY_WIDTH, TIMER_HZ};
+
+const FRAME_RATE: usize = TIMER_HZ as usize;
+
+pub fn host_key(key: char) -> Option<Key> {
+    #[rustfmt::skip]
+    const KEYS: [Key; 36] = [
+        Key::Key0, Key::Key1, Key::Key2, Key::Key3, Key::Key4, Key::Key5, Key::Key6, Key::Key7,
+        Key::Key8, Key::Key9, Key::A, Key::B, Key::C, Key::D, Key::E, Key::F, Key::G, Key::H,
+        Key::I, Key::J, Key::K, Key::L, Key::M, Key::N, Key::O, Key::P, Key::Q, Key::R, Key::S,
+        Key::T, Key::U, Key::V, Key::W, Key::X, Key::Y, Key::Z,
+    ];
+
+    key.to_digit(36).map(|i| KEYS[i as usize])
+}
+
+// 0RGB, as minifb wants it
+pub fn pixel_colours(palette: &Palette) -> [u32; 4] {
+    palette.colours.map(|colour| {
+        let (r, g, b) = colour.rgb();
+        (r as u32) << 16 | (g as u32) << 8 | b as u32
+    })
+}
+
+// Integer scaling, every CHIP-8 pixel becomes a `scale` x `scale` square
+pub fn draw(system: &System, colours: &[u32; 4], scale: usize, buffer: &mut [u32]) {
+    for (y, row) in buffer.chunks_exact_mut(DISPLAY_WIDTH * scale).enumerate() {
+        let line = &system.display()[(y / scale) * DISPLAY_WIDTH..][..DISPLAY_WIDTH];
+
+        for (x, pixel) in row.iter_mut().enumerate() {
+            let value = line[x / scale] & 0x3;
+
+            *pixel = match system.colour_map() {
+                Some(map) => {
+                    let (r, g, b) = map.colour(x / scale, y / scale, value != 0);
+                    (r as u32) << 16 | (g as u32) << 8 | b as u32
+                }
+                None => colours[value as usize],
+            };
+        }
+    }
+}
+
+pub fn run(
+    system: &mut System,
+    name: &str,
+    palette: Palette,
+    scale: usize,
+    keymap: &Keymap,
+    mut memory_log: Option<File>,
+) -> Result<(), String> {
+    let scale = scale.max(1);
+    let (width, height) = (DISPLAY_WIDTH * scale, DISPLAY_HEIGHT * scale);
+    let title = format!("Rusty-8 - CHIP-8 Emulator ({})", name);
+
+    let mut window =
+        Window::new(&title, width, height, WindowOptions::default()).map_err(|e| e.to_string())?;
+    window.set_target_fps(FRAME_RATE);
+
+    let colours = pixel_colours(&palette);
+
+    let keys: Vec<(Key, u8)> = keymap
+        .entries()
+        .into_iter()
+        .filter_map(|(host, code)| Some((host_key(host)?, code)))
+        .collect();
+
+    let mut buffer = vec![colours[0]; width * height];
+    let mut speed = Speed::new();
+    let mut status = String::new();
+
+    while window.is_open() && !window.is_key_down(Key::Escape) {
+        // Several host keys can share a CHIP-8 key
+        let mut pressed = [false; 16];
+        for (key, code) in keys.iter() {
+            pressed[*code as usize] |= window.is_key_down(*key);
+        }
+
+        for (code, down) in pressed.iter().enumerate() {
+            system.set_key(code as u8, *down);
+        }
+
+        if window.is_key_pressed(Key::P, KeyRepeat::No) {
+            speed.toggle_pause(system);
+        }
+
+        speed.fast_forward = window.is_key_down(Key::Tab);
+
+        if window.is_key_pressed(Key::F5, KeyRepeat::No) {
+            system.restart();
+        }
+
+        if window.is_key_pressed(Key::Equal, KeyRepeat::Yes) {
+            speed.faster(system);
+        }
+
+        if window.is_key_pressed(Key::Minus, KeyRepeat::Yes) {
+            speed.slower(system);
+        }
+
+        for _ in 0..speed.frames() {
+            system.run_frame();
+        }
+        speed.check_break(system);
+
+        if system.take_redraw() {
+            draw(system, &colours, scale, &mut buffer);
+        }
+
+        for event in system.take_memory_events() {
+            if let Some(ref mut log) = memory_log {
+                writeln!(log, "{}", system.symbols().annotate(event, event.pc))
+                    .map_err(|e| e.to_string())?;
+            }
+
+            if event.policy == Policy::Warn {
+                eprintln!("warning: {}", event);
+            }
+        }
+
+        speed.sample(system);
+
+        let new_status = format!("{} - {}", title, speed.status(system));
+
+        if new_status != status {
+            window.set_title(&new_status);
+            status = new_status;
+        }
+
+        window
+            .update_with_buffer(&buffer, width, height)
+            .map_err(|e| e.to_string())?;
+    }
+
+    Ok(())
+}
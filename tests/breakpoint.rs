@@ -0,0 +1,46 @@
+// The MIT License (MIT)
+
+// Copyright (c) 2021 AnonymousDapper
+
+use rusty_8::System;
+
+#[rustfmt::skip]
+static ROM: [u8; 6] = [
+    0x70, 0x01, // ADD V0 0x01
+    0x71, 0x01, // ADD V1 0x01
+    0x12, 0x00, // JP 0x200
+];
+
+#[test]
+fn breakpoint_stops_before_the_instruction() {
+    let mut system = System::new(100);
+    system.load_rom(&ROM).unwrap();
+    system.set_breakpoint(0x202, true);
+    system.run_frame();
+
+    assert_eq!(system.break_hit(), Some(0x202));
+    assert_eq!(system.pc(), 0x202);
+    assert_eq!((system.read_register(0), system.read_register(1)), (1, 0));
+
+    // Nothing runs until it is resumed
+    system.run_frame();
+    assert_eq!(system.read_register(0), 1);
+
+    system.resume();
+    system.run_frame();
+    assert_eq!(system.break_hit(), Some(0x202));
+    assert_eq!((system.read_register(0), system.read_register(1)), (2, 1));
+}
+
+#[test]
+fn breakpoints_toggle() {
+    let mut system = System::new(100);
+    system.load_rom(&ROM).unwrap();
+
+    system.toggle_breakpoint(0x204);
+    system.toggle_breakpoint(0x204);
+    system.run_frame();
+
+    assert_eq!(system.break_hit(), None);
+    assert!(system.breakpoints().is_empty());
+}
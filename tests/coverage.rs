@@ -0,0 +1,90 @@
+// The MIT License (MIT)
+
+// Copyright (c) 2021 AnonymousDapper
+
+use rusty_8::platform::Platform;
+use rusty_8::System;
+
+#[rustfmt::skip]
+static ROM: [u8; 16] = [
+    0xa3, 0x00, // 0x200 LD I 0x300
+    0xf1, 0x55, // 0x202 LD [I] V1
+    0xa2, 0x0e, // 0x204 LD I 0x20e
+    0xd0, 0x12, // 0x206 DRW V0 V1 0x2
+    0x12, 0x08, // 0x208 JP 0x208
+    0x22, 0x0e, // 0x20a CALL 0x20e, never runs
+    0x00, 0xee, // 0x20c RET
+    0xff, 0x81, // 0x20e sprite data
+];
+
+fn covered() -> System {
+    let mut system = System::new(rusty_8::DEFAULT_IPF);
+    system.enable_coverage();
+    system.load_rom(&ROM).unwrap();
+    system.run_frame();
+    system
+}
+
+#[test]
+fn tracks_execution_reads_and_writes() {
+    let system = covered();
+    let coverage = system.coverage().unwrap();
+
+    assert!(coverage.executed(0x200) && coverage.executed(0x201));
+    assert!(coverage.executed(0x208));
+    assert!(!coverage.executed(0x20a));
+
+    assert!(coverage.written(0x300) && coverage.written(0x301));
+    assert!(!coverage.written(0x302));
+
+    assert!(coverage.read(0x20e) && coverage.read(0x20f));
+    assert!(!coverage.read(0x200));
+}
+
+#[test]
+fn coverage_is_off_by_default() {
+    let mut system = System::new(rusty_8::DEFAULT_IPF);
+    system.load_rom(&ROM).unwrap();
+    system.run_frame();
+
+    assert!(system.coverage().is_none());
+}
+
+#[test]
+fn reports() {
+    let system = covered();
+    let coverage = system.coverage().unwrap();
+
+    let summary = coverage.summary(&system);
+    assert!(summary.contains("executed        10 bytes   62.5%"));
+    assert!(summary.contains("read             2 bytes   12.5%"));
+    assert!(summary.contains("untouched        4 bytes   25.0%"));
+
+    let map = coverage.map(&system);
+    assert!(map.contains("0x0200 xxxxxxxxxx....rr"));
+    assert!(map.contains("0x0300 ww"));
+
+    let listing = coverage.listing(&system, |addr| format!("op {:03x}", addr));
+    assert!(listing.contains("x-- 0x0200 | (0xa300) op 200"));
+    assert!(listing.contains("--- 0x020a | (0x220e) op 20a"));
+    assert!(listing.contains("-r- 0x020e | (0xff81) op 20e"));
+}
+
+#[test]
+fn code_after_chip8x_colour_opcodes_counts() {
+    #[rustfmt::skip]
+    let rom = [
+        0x02, 0xa0, // 0x300 Step background colour
+        0x60, 0x01, // 0x302 LD V0 0x01
+        0x13, 0x04, // 0x304 JP 0x304
+    ];
+
+    let mut system = System::new(rusty_8::DEFAULT_IPF);
+    system.set_platform(Platform::Chip8X);
+    system.enable_coverage();
+    system.load_rom(&rom).unwrap();
+    system.run_frame();
+
+    let summary = system.coverage().unwrap().summary(&system);
+    assert!(summary.contains("code             3 / 3 instructions 100.0%"));
+}
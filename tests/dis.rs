@@ -0,0 +1,114 @@
+// The MIT License (MIT)
+
+// Copyright (c) 2021 AnonymousDapper
+
+use rusty_8::dis::{strip_escapes, Disassembler};
+use rusty_8::display::Renderer;
+use rusty_8::layout::Layout;
+use rusty_8::symbols::Symbols;
+use rusty_8::System;
+
+#[rustfmt::skip]
+static ROM: [u8; 8] = [
+    0x22, 0x06, // CALL 0x206
+    0x12, 0x00, // JP 0x200
+    0x1f, 0xff, // JP 0xfff, outside the ROM
+    0x00, 0xee, // RET
+];
+
+#[test]
+fn jump_and_call_targets_are_labelled() {
+    let mut system = System::new(rusty_8::DEFAULT_IPF);
+    system.load_rom(&ROM).unwrap();
+
+    let mut dis = Disassembler::new();
+    dis.scan_labels(&system);
+
+    assert_eq!(dis.label(&system, 0x206), Some("sub_206"));
+    assert_eq!(dis.label(&system, 0x200), Some("loc_200"));
+    assert_eq!(dis.label(&system, 0xfff), None);
+    assert!(dis.mnemonic(&system, 0x200).contains("sub_206"));
+}
+
+#[test]
+fn view_scrolls_apart_from_pc() {
+    let mut system = System::new(rusty_8::DEFAULT_IPF);
+    system.load_rom(&ROM).unwrap();
+
+    let mut dis = Disassembler::new();
+    dis.scroll(&system, 2);
+    system.step();
+
+    assert_eq!(dis.selected(&system), 0x204);
+    dis.toggle_breakpoint(&mut system);
+    assert!(system.breakpoints().contains(&0x204));
+
+    dis.follow_pc();
+    assert_eq!(dis.selected(&system), 0x206);
+}
+
+#[test]
+fn patched_code_is_labelled_again() {
+    #[rustfmt::skip]
+    let rom = [
+        0x60, 0x12, // 0x200 LD V0 0x12
+        0x61, 0x0a, // 0x202 LD V1 0x0a
+        0xa2, 0x08, // 0x204 LD I 0x208
+        0xf1, 0x55, // 0x206 LD [I] V1, patches 0x208 into JP 0x20a
+        0x00, 0x00, // 0x208
+        0x00, 0xe0, // 0x20a CLS
+        0x12, 0x0c, // 0x20c JP 0x20c
+    ];
+
+    let mut system = System::new(rusty_8::DEFAULT_IPF);
+    system.enable_smc_detection();
+    system.load_rom(&rom).unwrap();
+
+    let mut dis = Disassembler::new();
+    dis.scan_labels(&system);
+    assert_eq!(dis.label(&system, 0x20a), None);
+
+    for _ in 0..5 {
+        system.step();
+        dis.trace(&system);
+    }
+
+    assert_eq!(system.pc(), 0x20a);
+    assert_eq!(system.memory().read_u16(0x208), 0x120a);
+    assert_eq!(dis.label(&system, 0x20a), Some("loc_20a"));
+}
+
+#[test]
+fn window_stays_inside_memory() {
+    let mut system = System::new(rusty_8::DEFAULT_IPF);
+    system.load_rom(&ROM).unwrap();
+
+    let name = "a_label_much_too_long_for_the_pane_it_is_shown_in_".repeat(2);
+    system.set_symbols(Symbols::parse(&format!("0x206 {}", name)).unwrap());
+
+    let layout = Layout::compute((200, 60), Renderer::Ascii, true);
+    let pane = layout.disassembly.unwrap();
+    let print = |dis: &Disassembler, system: &System| {
+        let mut out = Vec::new();
+        dis.print_dis(system, &layout, &mut out);
+        strip_escapes(&String::from_utf8(out).unwrap())
+    };
+
+    // The window would start below address 0
+    let mut dis = Disassembler::new();
+    dis.scroll(&system, -0x100);
+    assert_eq!(dis.selected(&system), 0x000);
+    assert!(print(&dis, &system).contains(">0x0000 |"));
+
+    // And past the end of RAM
+    dis.scroll(&system, -1);
+    let text = print(&dis, &system);
+    assert!(text.contains(">0x0ffe |"));
+    assert!(!text.contains("0x0000 |"));
+
+    // Long names are cut at the edge of the pane
+    dis.follow_pc();
+    let text = print(&dis, &system);
+    assert!(text.contains(&format!("  {}", &name[..pane.width - 2])));
+    assert!(!text.contains(&name[..pane.width - 1]));
+}
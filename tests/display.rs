@@ -0,0 +1,194 @@
+// The MIT License (MIT)
+
+// Copyright (c) 2021 AnonymousDapper
+
+use std::collections::HashMap;
+use std::io::{self, Write};
+use std::sync::{Arc, Mutex};
+
+use rusty_8::display::{Renderer, Screen, DISPLAY_HEIGHT, DISPLAY_WIDTH};
+
+#[derive(Clone, Default)]
+struct Shared(Arc<Mutex<Vec<u8>>>);
+
+impl Write for Shared {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.0.lock().unwrap().extend_from_slice(buf);
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+// Cells written inside the display, keyed by (row, col) from its top left corner, with the SGR
+// parameters each was drawn with
+type Cells = HashMap<(usize, usize), (String, char)>;
+
+// Known frame: plane 1 at (0, 0) and (1, 3), plane 2 at (5, 1), both planes in the far corner
+fn frame() -> Vec<u8> {
+    let mut buffer = vec![0; DISPLAY_WIDTH * DISPLAY_HEIGHT];
+
+    for &(x, y, value) in &[(0, 0, 1), (1, 3, 1), (5, 1, 2), (63, 31, 3)] {
+        buffer[x + y * DISPLAY_WIDTH] = value;
+    }
+
+    buffer
+}
+
+fn render(renderer: Renderer) -> Cells {
+    let out = Shared::default();
+    let mut screen = Screen::new("test", renderer, false);
+    screen.set_term_size((200, 60));
+    screen.set_output(Box::new(out.clone()));
+    screen.write_display(&frame());
+
+    let area = screen.layout().display;
+    let (width, height) = renderer.size(DISPLAY_WIDTH, DISPLAY_HEIGHT);
+    assert_eq!((area.width, area.height), (width, height));
+
+    let text = String::from_utf8(out.0.lock().unwrap().clone()).unwrap();
+    let mut chars = text.chars();
+    let (mut row, mut col, mut sgr) = (0, 0, String::new());
+    let mut cells = Cells::new();
+
+    while let Some(c) = chars.next() {
+        if c == '\x1b' {
+            assert_eq!(chars.next(), Some('['));
+
+            let mut params = String::new();
+            let end = loop {
+                match chars.next().unwrap() {
+                    c if c.is_ascii_alphabetic() => break c,
+                    c => params.push(c),
+                }
+            };
+
+            match end {
+                'H' => {
+                    let mut position = params.split(';').map(|n| n.parse::<usize>().unwrap());
+                    row = position.next().unwrap();
+                    col = position.next().unwrap();
+                }
+                'm' if params == "0" => sgr.clear(),
+                'm' => sgr = params,
+                _ => panic!("unexpected escape {:?}{}", params, end),
+            }
+        } else {
+            assert!(
+                row >= area.row && row < area.row + area.height,
+                "row {}",
+                row
+            );
+            assert!(
+                col >= area.col && col < area.col + area.width,
+                "col {}",
+                col
+            );
+
+            cells.insert((row - area.row, col - area.col), (sgr.clone(), c));
+            col += 1;
+        }
+    }
+
+    // Every cell of the display is drawn, and nothing outside it
+    assert_eq!(cells.len(), width * height);
+    cells
+}
+
+fn cell(cells: &Cells, row: usize, col: usize) -> (&str, char) {
+    let (sgr, c) = &cells[&(row, col)];
+    (sgr, *c)
+}
+
+#[test]
+fn ascii_draws_a_character_per_pixel() {
+    let cells = render(Renderer::Ascii);
+
+    assert_eq!(cell(&cells, 0, 0), ("", '#'));
+    assert_eq!(cell(&cells, 0, 1), ("", ' '));
+    assert_eq!(cell(&cells, 3, 1), ("", '#'));
+    assert_eq!(cell(&cells, 1, 5), ("", '#'));
+    assert_eq!(cell(&cells, 31, 63), ("", '#'));
+    assert_eq!(cell(&cells, 31, 62), ("", ' '));
+}
+
+#[test]
+fn block_draws_two_columns_per_pixel() {
+    let cells = render(Renderer::Block);
+
+    assert_eq!(cell(&cells, 0, 0), ("97", '█'));
+    assert_eq!(cell(&cells, 0, 1), ("97", '█'));
+    assert_eq!(cell(&cells, 0, 2), ("30", '█'));
+    assert_eq!(cell(&cells, 3, 2), ("97", '█'));
+    assert_eq!(cell(&cells, 1, 10), ("93", '█'));
+    assert_eq!(cell(&cells, 1, 11), ("93", '█'));
+    assert_eq!(cell(&cells, 31, 126), ("96", '█'));
+    assert_eq!(cell(&cells, 31, 127), ("96", '█'));
+}
+
+#[test]
+fn half_block_puts_the_upper_pixel_in_the_foreground() {
+    let cells = render(Renderer::HalfBlock);
+
+    // (0, 0) on top of (0, 1)
+    assert_eq!(cell(&cells, 0, 0), ("97;40", '▀'));
+    // (1, 2) on top of (1, 3)
+    assert_eq!(cell(&cells, 1, 1), ("30;107", '▀'));
+    // (5, 0) on top of (5, 1)
+    assert_eq!(cell(&cells, 0, 5), ("30;103", '▀'));
+    assert_eq!(cell(&cells, 15, 63), ("30;106", '▀'));
+    assert_eq!(cell(&cells, 15, 62), ("30;40", '▀'));
+}
+
+#[test]
+fn braille_maps_each_dot_to_its_bit() {
+    let cells = render(Renderer::Braille);
+
+    // Dot 1 at (0, 0) and dot 8 at (1, 3)
+    assert_eq!(cell(&cells, 0, 0), ("97;40", '\u{2881}'));
+    // Empty cells keep the plane 1 colour
+    assert_eq!(cell(&cells, 0, 1), ("97;40", '\u{2800}'));
+    // Dot 5 at (1, 1) in the cell starting at x = 4
+    assert_eq!(cell(&cells, 0, 2), ("93;40", '\u{2810}'));
+    // Dot 8 in the bottom right cell
+    assert_eq!(cell(&cells, 7, 31), ("96;40", '\u{2880}'));
+}
+
+#[test]
+fn braille_dot_order() {
+    let mut buffer = vec![0; DISPLAY_WIDTH * DISPLAY_HEIGHT];
+    let out = Shared::default();
+    let mut screen = Screen::new("test", Renderer::Braille, false);
+    screen.set_term_size((200, 60));
+    screen.set_output(Box::new(out.clone()));
+
+    // One dot at a time in the top left cell, in braille dot order
+    let dots = [
+        (0, 0),
+        (0, 1),
+        (0, 2),
+        (1, 0),
+        (1, 1),
+        (1, 2),
+        (0, 3),
+        (1, 3),
+    ];
+
+    for (bit, &(x, y)) in dots.iter().enumerate() {
+        buffer.iter_mut().for_each(|p| *p = 0);
+        buffer[x + y * DISPLAY_WIDTH] = 1;
+
+        out.0.lock().unwrap().clear();
+        screen.write_display(&buffer);
+
+        let expected = std::char::from_u32(0x2800 + (1 << bit)).unwrap();
+        let text = String::from_utf8(out.0.lock().unwrap().clone()).unwrap();
+        assert!(
+            text.contains(&format!("97;40m{}", expected)),
+            "dot {}",
+            bit + 1
+        );
+    }
+}
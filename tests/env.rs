@@ -0,0 +1,115 @@
+// The MIT License (MIT)
+
+// Copyright (c) 2021 AnonymousDapper
+
+use rusty_8::compare::Compare;
+use rusty_8::env::Environment;
+use rusty_8::{GuestError, System};
+
+// Scores a point each time key 5 goes down
+#[rustfmt::skip]
+static ROM: [u8; 18] = [
+    0x61, 0x05, // 0x200 LD V1 0x05
+    0xe1, 0x9e, // 0x202 SKP V1
+    0x12, 0x02, // 0x204 JP 0x202
+    0x70, 0x01, // 0x206 ADD V0 0x01
+    0xa3, 0x00, // 0x208 LD I 0x300
+    0xf0, 0x55, // 0x20a LD [I] V0
+    0xe1, 0xa1, // 0x20c SKNP V1
+    0x12, 0x0c, // 0x20e JP 0x20c
+    0x12, 0x02, // 0x210 JP 0x202
+];
+
+fn env() -> Environment {
+    let mut env = Environment::new(System::new(rusty_8::DEFAULT_IPF), &ROM).unwrap();
+    env.add_reward(0x300, 1.0);
+    env.add_done(0x300, Compare::Ge, 3);
+    env.set_frame_skip(2);
+    env
+}
+
+#[test]
+fn rewards_until_done() {
+    let mut env = env();
+    let observation = env.reset();
+    assert_eq!(observation.len(), 64 * 32);
+
+    let (_, reward, done) = env.step(&[5]);
+    assert_eq!((reward, done), (1.0, false));
+
+    // Still held, so no new point
+    let (_, reward, _) = env.step(&[5]);
+    assert_eq!(reward, 0.0);
+
+    let (_, reward, _) = env.step(&[]);
+    assert_eq!(reward, 0.0);
+
+    env.step(&[5]);
+    env.step(&[]);
+    let (_, reward, done) = env.step(&[5, 7]);
+    assert_eq!((reward, done), (1.0, true));
+    // The last step stops as soon as the episode is over
+    assert_eq!(env.system().frames(), 11);
+}
+
+#[test]
+fn reset_starts_over() {
+    let mut env = env();
+    env.step(&[5]);
+    env.reset();
+
+    assert_eq!(env.system().frames(), 0);
+    assert_eq!(env.system().memory().read_u8(0x300), 0);
+    assert!(!env.is_done());
+}
+
+#[test]
+fn environments_run_in_parallel() {
+    let threads: Vec<_> = (1..=4u8)
+        .map(|presses| {
+            let mut env = env();
+            std::thread::spawn(move || {
+                let mut total = 0.0;
+                for _ in 0..presses {
+                    total += env.step(&[5]).1;
+                    env.step(&[]);
+                }
+                total
+            })
+        })
+        .collect();
+
+    let totals: Vec<f64> = threads.into_iter().map(|t| t.join().unwrap()).collect();
+    assert_eq!(totals, [1.0, 2.0, 3.0, 3.0]);
+}
+
+#[test]
+fn wrapping_scores_and_faults() {
+    #[rustfmt::skip]
+    let rom = [
+        0x60, 0xff, // 0x200 LD V0 0xff
+        0xa3, 0x00, // 0x202 LD I 0x300
+        0xf0, 0x55, // 0x204 LD [I] V0
+        0xf1, 0x0a, // 0x206 LD V1 K
+        0x60, 0x00, // 0x208 LD V0 0x00
+        0xa3, 0x00, // 0x20a LD I 0x300
+        0xf0, 0x55, // 0x20c LD [I] V0
+        0xe0, 0xff, // 0x20e Not an instruction
+    ];
+    let mut env = Environment::new(System::new(rusty_8::DEFAULT_IPF), &rom).unwrap();
+    env.add_reward(0x300, 1.0);
+
+    let (_, reward, done) = env.step(&[]);
+    assert_eq!((reward, done), (-1.0, false));
+
+    // 255 to 0 is one more, and the bad opcode ends the episode instead of panicking
+    let (_, reward, done) = env.step(&[3]);
+    assert_eq!((reward, done), (1.0, true));
+    assert_eq!(
+        env.error(),
+        Some(GuestError::IllegalOpcode {
+            pc: 0x20e,
+            op: 0xe0ff
+        })
+    );
+}
@@ -0,0 +1,106 @@
+// The MIT License (MIT)
+
+// Copyright (c) 2021 AnonymousDapper
+
+use rusty_8::flow::FlowGraph;
+use rusty_8::platform::Platform;
+use rusty_8::System;
+
+#[rustfmt::skip]
+static ROM: [u8; 16] = [
+    0x60, 0x7b, // 0x200 LD V0 0x7b
+    0x22, 0x0a, // 0x202 CALL 0x20a
+    0x30, 0x00, // 0x204 SE V0 0x00
+    0x12, 0x02, // 0x206 JP 0x202
+    0xb3, 0x00, // 0x208 JP V0 0x300
+    0xa3, 0x00, // 0x20a LD I 0x300
+    0xf0, 0x33, // 0x20c BCD V0
+    0x00, 0xee, // 0x20e RET
+];
+
+fn graph() -> (System, FlowGraph) {
+    let mut system = System::new(rusty_8::DEFAULT_IPF);
+    system.load_rom(&ROM).unwrap();
+    let graph = FlowGraph::build(&system);
+
+    (system, graph)
+}
+
+#[test]
+fn blocks_split_at_control_flow() {
+    let (_, graph) = graph();
+    let starts: Vec<u16> = graph.blocks().map(|block| block.start).collect();
+
+    assert_eq!(starts, [0x200, 0x202, 0x204, 0x206, 0x208, 0x20a]);
+    assert_eq!(graph.block(0x200).unwrap().successors, [0x202]);
+    assert_eq!(graph.block(0x202).unwrap().calls, [0x20a]);
+    assert_eq!(graph.block(0x20a).unwrap().end, 0x210);
+    assert!(graph.block(0x20a).unwrap().successors.is_empty());
+}
+
+#[test]
+fn skips_have_two_successors() {
+    let (_, graph) = graph();
+
+    assert_eq!(graph.block(0x204).unwrap().successors, [0x206, 0x208]);
+}
+
+#[test]
+fn indirect_jumps_are_unresolved() {
+    let (_, graph) = graph();
+
+    assert!(graph.block(0x208).unwrap().unresolved);
+    assert!(!graph.block(0x206).unwrap().unresolved);
+}
+
+#[test]
+fn one_cluster_per_subroutine() {
+    let (system, graph) = graph();
+    let subroutines = graph.subroutines();
+
+    assert_eq!(subroutines.len(), 2);
+    assert_eq!(subroutines[0].blocks, [0x200, 0x202, 0x204, 0x206, 0x208]);
+    assert_eq!(subroutines[1].blocks, [0x20a]);
+
+    let dot = graph.to_dot(&system, |addr| format!("op {:03x}", addr));
+    assert!(dot.starts_with("digraph cfg {"));
+    assert_eq!(dot.matches("subgraph cluster_").count(), 2);
+    assert!(dot.contains("label=\"sub_20a\""));
+    assert!(dot.contains("b_208 -> u_208"));
+}
+
+fn graph_on(platform: Platform, rom: &[u8]) -> FlowGraph {
+    let mut system = System::new(rusty_8::DEFAULT_IPF);
+    system.set_platform(platform);
+    system.load_rom(rom).unwrap();
+
+    FlowGraph::build(&system)
+}
+
+#[test]
+fn chip8e_system_calls_fall_through() {
+    #[rustfmt::skip]
+    let graph = graph_on(Platform::Chip8E, &[
+        0x00, 0xf2, // 0x200 No-op
+        0x01, 0x51, // 0x202 Wait for DT
+        0x12, 0x04, // 0x204 JP 0x204
+    ]);
+    let block = graph.block(0x200).unwrap();
+
+    // The jump's target starts a block of its own
+    assert_eq!(block.end, 0x204);
+    assert_eq!(block.successors, [0x204]);
+}
+
+#[test]
+fn chip8x_background_step_falls_through() {
+    #[rustfmt::skip]
+    let graph = graph_on(Platform::Chip8X, &[
+        0x02, 0xa0, // 0x300 Step background colour
+        0x13, 0x02, // 0x302 JP 0x302
+    ]);
+    let block = graph.block(0x300).unwrap();
+
+    assert_eq!(block.end, 0x302);
+    assert_eq!(block.successors, [0x302]);
+}
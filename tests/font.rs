@@ -0,0 +1,128 @@
+// The MIT License (MIT)
+
+// Copyright (c) 2021 AnonymousDapper
+
+use rusty_8::font::{Font, FontSet};
+use rusty_8::platform::Platform;
+use rusty_8::{GuestError, System};
+
+#[rustfmt::skip]
+static GLYPH_ROM: [u8; 6] = [
+    0x60, 0x0b, // LD V0 0x0b
+    0xf0, 0x29, // LD F V0
+    0xf0, 0x30, // LD HF V0
+];
+
+#[test]
+fn glyphs_follow_font_address() {
+    let mut system = System::new(rusty_8::DEFAULT_IPF);
+    system.set_platform(Platform::SuperChip);
+    system.load_rom(&GLYPH_ROM).unwrap();
+
+    system.step();
+    system.step();
+    assert_eq!(system.ir(), 0x050 + 0xb * 5);
+    assert_eq!(system.memory().read_u8(system.ir()), 0xe0);
+
+    system.step();
+    assert_eq!(system.ir(), 0x050 + 80 + 0xb * 10);
+    assert_eq!(system.memory().read_u8(system.ir()), 0xfc);
+}
+
+#[test]
+fn platforms_pick_their_glyphs() {
+    let mut system = System::new(rusty_8::DEFAULT_IPF);
+    system.load_rom(&GLYPH_ROM).unwrap();
+
+    // The VIP's B has a flat left side
+    assert_eq!(system.memory().read_u8(0x050 + 0xb * 5), 0xf0);
+
+    assert!(system.set_font(FontSet::Eti660.font(), 0x000));
+    system.restart();
+    system.step();
+    system.step();
+
+    assert_eq!(system.ir(), 0xb * 5);
+    assert_eq!(system.memory().read_u8(system.ir()), 0x80);
+    assert_eq!(system.memory().map().region(0x000).unwrap().name, "font");
+    assert_eq!(system.memory().map().region(0x0ef).unwrap().name, "font");
+    assert_eq!(
+        system.memory().map().region(0x0f0).unwrap().name,
+        "interpreter"
+    );
+}
+
+#[test]
+fn custom_font_from_bytes() {
+    let small: Vec<u8> = (0..80).collect();
+
+    assert!(Font::from_bytes(&small[..79]).is_none());
+
+    // Without big glyphs of its own the SCHIP ones follow
+    let font = Font::from_bytes(&small).unwrap();
+    assert_eq!(font.len(), 80 + 160);
+    assert_eq!(font.bytes()[80..], FontSet::Schip.font().bytes()[80..]);
+
+    let mut system = System::new(rusty_8::DEFAULT_IPF);
+    system.set_platform(Platform::XoChip);
+    assert!(system.set_font(font.clone(), 0x100));
+    assert!(!system.set_font(font, 0x1c0));
+
+    system.load_rom(&GLYPH_ROM).unwrap();
+    system.step();
+    system.step();
+
+    assert_eq!(system.ir(), 0x100 + 0xb * 5);
+    assert_eq!(system.memory().read_u8(system.ir()), 0xb * 5);
+
+    system.step();
+    assert_eq!(system.error(), None);
+    assert_eq!(system.ir(), 0x100 + 80 + 0xb * 10);
+    assert_eq!(system.memory().read_u8(system.ir()), 0xfc);
+}
+
+#[test]
+fn index_wraps_around_memory() {
+    let mut system = System::new(rusty_8::DEFAULT_IPF);
+    #[rustfmt::skip]
+    system.load_rom(&[
+        0xaf, 0xff, // LD I 0xfff
+        0x60, 0xff, // LD V0 0xff
+        0xf0, 0x1e, // 0x204 ADD I V0
+        0xf0, 0x65, // LD V0 [I]
+        0x60, 0xff, // LD V0 0xff
+        0x12, 0x04, // JP 0x204
+    ]).unwrap();
+
+    system.step();
+    system.step();
+    system.step();
+    assert_eq!(system.ir(), 0x0fe);
+
+    // Enough additions to have run past the top of a u16
+    for _ in 0..4 * 300 {
+        system.step();
+    }
+    assert!(system.ir() < 0x1000);
+    assert_eq!(system.error(), None);
+}
+
+#[test]
+fn big_glyphs_need_an_schip_platform() {
+    let mut system = System::new(rusty_8::DEFAULT_IPF);
+    system.load_rom(&GLYPH_ROM).unwrap();
+
+    system.step();
+    system.step();
+    assert_eq!(system.error(), None);
+
+    system.step();
+    assert!(system.is_halted());
+    assert_eq!(
+        system.error(),
+        Some(GuestError::IllegalOpcode {
+            pc: 0x204,
+            op: 0xf030
+        })
+    );
+}
@@ -0,0 +1,97 @@
+// The MIT License (MIT)
+
+// Copyright (c) 2021 AnonymousDapper
+
+use rusty_8::display::Renderer;
+use rusty_8::layout::{self, Layout, Rect};
+
+fn rect(row: usize, col: usize, width: usize, height: usize) -> Option<Rect> {
+    Some(Rect {
+        row,
+        col,
+        width,
+        height,
+    })
+}
+
+#[test]
+fn no_panes_without_debug() {
+    let layout = Layout::compute((200, 60), Renderer::Ascii, false);
+
+    assert_eq!(layout.title_row, 3);
+    assert_eq!(layout.frame, rect(4, 8, 66, 34).unwrap());
+    assert_eq!(layout.display, rect(5, 9, 64, 32).unwrap());
+    assert_eq!(layout.registers, None);
+    assert_eq!(layout.stack, None);
+    assert_eq!(layout.disassembly, None);
+    assert_eq!(layout.memory, None);
+    assert_eq!(layout.sprite, None);
+    assert_eq!(layout.status_row, 38);
+}
+
+#[test]
+fn wide_terminal_fits_every_pane_beside_the_display() {
+    let layout = Layout::compute((200, 60), Renderer::Ascii, true);
+
+    assert_eq!(
+        layout.registers,
+        rect(4, 78, layout::REGISTERS_WIDTH, layout::REGISTERS_HEIGHT)
+    );
+    assert_eq!(layout.stack, rect(4, 96, layout::STACK_WIDTH, 24));
+    // Narrowed to leave room for the memory pane
+    assert_eq!(layout.disassembly, rect(4, 112, 54, 34));
+    assert_eq!(layout.memory, rect(4, 170, layout::MEMORY_WIDTH, 18));
+    // Under the memory pane
+    assert_eq!(layout.sprite, rect(23, 170, layout::SPRITE_WIDTH, 18));
+    assert_eq!(layout.status_row, 41);
+}
+
+#[test]
+fn small_display_puts_panes_below_when_more_fit() {
+    let layout = Layout::compute((120, 40), Renderer::Braille, true);
+
+    assert_eq!(layout.frame, rect(4, 8, 34, 10).unwrap());
+    assert_eq!(layout.display, rect(5, 9, 32, 8).unwrap());
+    assert_eq!(layout.registers, rect(15, 1, 14, 24));
+    assert_eq!(layout.stack, rect(15, 19, 12, 24));
+    assert_eq!(layout.disassembly, rect(15, 35, 51, 26));
+    assert_eq!(layout.memory, rect(15, 90, 31, 18));
+    assert_eq!(layout.sprite, None);
+    // Clamped to the terminal
+    assert_eq!(layout.status_row, 40);
+}
+
+#[test]
+fn short_terminal_uses_compact_registers() {
+    let layout = Layout::compute((110, 20), Renderer::Ascii, true);
+
+    assert_eq!(layout.title_row, 1);
+    assert_eq!(
+        layout.registers,
+        rect(
+            2,
+            78,
+            layout::REGISTERS_COMPACT_WIDTH,
+            layout::REGISTERS_COMPACT_HEIGHT
+        )
+    );
+    assert_eq!(layout.stack, None);
+    assert_eq!(layout.disassembly, None);
+    assert_eq!(layout.status_row, 20);
+}
+
+#[test]
+fn below_minimum_size_drops_margins_and_panes() {
+    // Narrower and shorter than the block display's frame
+    let layout = Layout::compute((80, 24), Renderer::Block, true);
+
+    assert_eq!(layout.title_row, 1);
+    assert_eq!(layout.frame, rect(2, 1, 132, 34).unwrap());
+    assert_eq!(layout.display, rect(3, 3, 128, 32).unwrap());
+    assert_eq!(layout.registers, None);
+    assert_eq!(layout.stack, None);
+    assert_eq!(layout.disassembly, None);
+    assert_eq!(layout.memory, None);
+    assert_eq!(layout.sprite, None);
+    assert_eq!(layout.status_row, 24);
+}
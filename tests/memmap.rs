@@ -0,0 +1,96 @@
+// The MIT License (MIT)
+
+// Copyright (c) 2021 AnonymousDapper
+
+use rusty_8::memmap::{Access, MemoryMap, Policy};
+use rusty_8::platform::Platform;
+use rusty_8::{GuestError, System};
+
+#[rustfmt::skip]
+static FONT_WRITE_ROM: [u8; 8] = [
+    0x60, 0xff, // LD V0 0xff
+    0xa0, 0x50, // LD I 0x050
+    0xf0, 0x55, // LD [I] V0
+    0x12, 0x06, // JP 0x206
+];
+
+#[test]
+fn regions_follow_platform() {
+    let vip = MemoryMap::for_platform(Platform::Chip8);
+    let names: Vec<_> = vip.regions().iter().map(|r| r.name).collect();
+
+    assert_eq!(
+        names,
+        [
+            "font",
+            "interpreter",
+            "program",
+            "stack",
+            "variables",
+            "display"
+        ]
+    );
+    assert_eq!(vip.region(0xe9f).unwrap().name, "program");
+    assert_eq!(vip.region(0xea0).unwrap().name, "stack");
+    assert_eq!(vip.region(0xfff).unwrap().name, "display");
+
+    let schip = MemoryMap::for_platform(Platform::SuperChip);
+    assert_eq!(schip.region(0xfff).unwrap().name, "program");
+}
+
+#[test]
+fn font_write_warns_by_default() {
+    let mut system = System::new(rusty_8::DEFAULT_IPF);
+    system.load_rom(&FONT_WRITE_ROM).unwrap();
+    system.run_frame();
+
+    let events = system.take_memory_events();
+
+    assert_eq!(events.len(), 1);
+    assert_eq!(events[0].access, Access::Write);
+    assert_eq!((events[0].addr, events[0].pc), (0x050, 0x204));
+    assert_eq!(events[0].policy, Policy::Warn);
+    assert_eq!(system.memory().read_u8(0x050), 0xff);
+    assert!(!system.is_halted());
+}
+
+#[test]
+fn strict_font_write_faults() {
+    let mut system = System::new(rusty_8::DEFAULT_IPF);
+    system.memory_map_mut().set_strict();
+    system.load_rom(&FONT_WRITE_ROM).unwrap();
+    system.run_frame();
+
+    assert!(system.is_halted());
+    assert_eq!(
+        system.error(),
+        Some(GuestError::AccessFault {
+            access: Access::Write,
+            addr: 0x050,
+            pc: 0x204,
+            region: "font"
+        })
+    );
+    assert_eq!(system.memory().read_u8(0x050), 0xf0);
+}
+
+#[test]
+fn executing_data_faults() {
+    let mut system = System::new(rusty_8::DEFAULT_IPF);
+    assert!(system
+        .memory_map_mut()
+        .set_policy("variables", Access::Execute, Policy::Fault));
+
+    system.load_rom(&[0x1e, 0xd0]).unwrap(); // JP 0xed0
+    system.run_frame();
+
+    assert_eq!(
+        system.error(),
+        Some(GuestError::AccessFault {
+            access: Access::Execute,
+            addr: 0xed0,
+            pc: 0xed0,
+            region: "variables"
+        })
+    );
+}
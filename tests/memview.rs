@@ -0,0 +1,50 @@
+// The MIT License (MIT)
+
+// Copyright (c) 2021 AnonymousDapper
+
+use rusty_8::memview::{pending_access, MemoryView};
+use rusty_8::System;
+
+#[rustfmt::skip]
+static ROM: [u8; 8] = [
+    0xa3, 0x00, // LD I 0x300
+    0xd0, 0x15, // DRW V0 V1 0x5
+    0xf2, 0x55, // LD [I] V2
+    0xf0, 0x33, // BCD V0
+];
+
+#[test]
+fn pending_access_covers_touched_bytes() {
+    let mut system = System::new(rusty_8::DEFAULT_IPF);
+    system.load_rom(&ROM).unwrap();
+
+    assert_eq!(pending_access(&system), None);
+    system.step();
+    assert_eq!(pending_access(&system), Some((0x300..0x305, false)));
+    system.step();
+    assert_eq!(pending_access(&system), Some((0x300..0x303, true)));
+
+    // The VIP's Fx55 leaves I past the last register stored
+    system.step();
+    assert_eq!(pending_access(&system), Some((0x303..0x306, true)));
+}
+
+#[test]
+fn edit_mode_patches_memory() {
+    let mut system = System::new(rusty_8::DEFAULT_IPF);
+    system.load_rom(&ROM).unwrap();
+    system.step();
+
+    let mut view = MemoryView::new();
+    view.set_editing(&system, true);
+
+    // Two digits per byte, then the cursor moves on; down moves a whole row
+    assert!(view.input(&mut system, b"ab1"));
+    assert!(view.input(&mut system, b"2\x1b[B\x1b[Dff"));
+    assert_eq!(system.memory().read_u8(0x300), 0xab);
+    assert_eq!(system.memory().read_u8(0x301), 0x12);
+    assert_eq!(system.memory().read_u8(0x309), 0xff);
+
+    assert!(!view.input(&mut system, b"\x1b"));
+    assert!(!view.is_editing());
+}
@@ -0,0 +1,98 @@
+// The MIT License (MIT)
+
+// Copyright (c) 2021 AnonymousDapper
+
+use rusty_8::palette::{Colour, Palette};
+
+#[test]
+fn every_name_is_a_palette() {
+    for name in Palette::NAMES {
+        assert!(Palette::from_name(name).is_some(), "{}", name);
+    }
+
+    assert_eq!(Palette::from_name("default"), Some(Palette::default()));
+}
+
+#[test]
+fn unknown_name_is_rejected() {
+    assert_eq!(Palette::from_name("nope"), None);
+    assert_eq!(Palette::from_name(""), None);
+}
+
+#[test]
+fn names_fall_back_to_hex_lists() {
+    let palette = Palette::from_name("#000000,ffffff").unwrap();
+
+    assert_eq!(palette.background(), Colour::Rgb(0, 0, 0));
+    assert_eq!(palette.pixel(1), Colour::Rgb(0xff, 0xff, 0xff));
+}
+
+#[test]
+fn hex_list_fills_missing_planes_with_the_first_foreground() {
+    let palette = Palette::from_hex_list("102030, a0b0c0").unwrap();
+    let (background, foreground) = (Colour::Rgb(0x10, 0x20, 0x30), Colour::Rgb(0xa0, 0xb0, 0xc0));
+    assert_eq!(
+        palette.colours,
+        [background, foreground, foreground, foreground]
+    );
+
+    let palette = Palette::from_hex_list("000000,ff0000,00ff00").unwrap();
+    assert_eq!(palette.pixel(3), Colour::Rgb(0xff, 0, 0));
+}
+
+#[test]
+fn hex_list_errors() {
+    // Bad hex digits and lengths
+    assert_eq!(Palette::from_hex_list("00000g,ffffff"), None);
+    assert_eq!(Palette::from_hex_list("#12345,ffffff"), None);
+    assert_eq!(Palette::from_hex_list("000000,fffffff"), None);
+    assert_eq!(Palette::from_hex_list("000000,"), None);
+
+    // Too few or too many colours
+    assert_eq!(Palette::from_hex_list("000000"), None);
+    assert_eq!(
+        Palette::from_hex_list("000000,111111,222222,333333,444444"),
+        None
+    );
+}
+
+#[test]
+fn xo_chip_planes_get_their_own_colours() {
+    let palette = Palette::from_hex_list("000000,ff0000,00ff00,0000ff").unwrap();
+
+    assert_eq!(palette.pixel(0), Colour::Rgb(0, 0, 0));
+    assert_eq!(palette.pixel(1), Colour::Rgb(0xff, 0, 0));
+    assert_eq!(palette.pixel(2), Colour::Rgb(0, 0xff, 0));
+    assert_eq!(palette.pixel(3), Colour::Rgb(0, 0, 0xff));
+
+    let palette = Palette::default();
+    assert_eq!(palette.pixel(1), Colour::Ansi(97));
+    assert_eq!(palette.pixel(2), Colour::Ansi(93));
+    assert_eq!(palette.pixel(3), Colour::Ansi(96));
+
+    // Only the two plane bits pick the colour
+    assert_eq!(palette.pixel(0x5), palette.pixel(1));
+}
+
+#[test]
+fn blend_mixes_each_channel() {
+    let (black, white) = (Colour::Rgb(0, 0, 0), Colour::Rgb(0xff, 0xff, 0xff));
+
+    assert_eq!(black.blend(white, 0), black);
+    assert_eq!(black.blend(white, 255), white);
+    assert_eq!(black.blend(white, 128), Colour::Rgb(128, 128, 128));
+    assert_eq!(
+        Colour::Rgb(0xff, 0, 0x40).blend(Colour::Rgb(0, 0xff, 0x40), 51),
+        Colour::Rgb(204, 51, 0x40)
+    );
+}
+
+#[test]
+fn blend_of_ansi_colours_is_a_single_grey_step() {
+    let (black, white) = (Colour::Ansi(30), Colour::Ansi(97));
+
+    assert_eq!(black.blend(white, 0), black);
+    assert_eq!(black.blend(white, 255), white);
+    assert_eq!(black.blend(white, 100), Colour::Ansi(90));
+    assert_eq!(Colour::Rgb(0, 0, 0).blend(white, 100), Colour::Ansi(90));
+}
@@ -0,0 +1,125 @@
+// The MIT License (MIT)
+
+// Copyright (c) 2021 AnonymousDapper
+
+use rusty_8::platform::Platform;
+use rusty_8::vp590::COLOURS;
+use rusty_8::System;
+
+fn boot(platform: Platform, rom: &[u8]) -> System {
+    let mut system = System::new(rusty_8::DEFAULT_IPF);
+    system.set_platform(platform);
+    system.load_rom(rom).unwrap();
+    system
+}
+
+fn steps(system: &mut System, count: usize) {
+    for _ in 0..count {
+        system.step();
+    }
+}
+
+#[test]
+fn eti660_loads_at_0x600() {
+    let system = boot(Platform::Eti660, &[0x16, 0x00]);
+
+    assert_eq!(system.pc(), 0x600);
+    assert_eq!(system.memory().read_u16(0x600), 0x1600);
+    assert_eq!(system.memory().read_u16(0x200), 0);
+}
+
+#[test]
+fn entry_address_is_configurable() {
+    let mut system = System::new(rusty_8::DEFAULT_IPF);
+    system.set_load_address(0x400);
+    system.set_entry_address(0x402);
+    system.load_rom(&[0x00, 0x00, 0x60, 0x2a]).unwrap();
+
+    assert_eq!(system.pc(), 0x402);
+    steps(&mut system, 1);
+    assert_eq!(system.read_register(0), 0x2a);
+}
+
+#[rustfmt::skip]
+static CHIP8E_ROM: [u8; 12] = [
+    0x60, 0x05, // LD V0 0x05
+    0x61, 0x03, // LD V1 0x03
+    0x50, 0x11, // SGT V0 V1
+    0x62, 0x01, // LD V2 0x01 (skipped)
+    0xbf, 0x02, // JF 0x02
+    0x63, 0x01, // LD V3 0x01 (skipped)
+];
+
+#[test]
+fn chip8e_opcodes() {
+    let mut system = boot(Platform::Chip8E, &CHIP8E_ROM);
+    steps(&mut system, 4);
+
+    assert_eq!(system.read_register(2), 0);
+    assert_eq!(system.read_register(3), 0);
+    assert_eq!(system.pc(), 0x20c);
+}
+
+#[test]
+fn chip8e_delay_waits_for_timer() {
+    // LD V0 0x02, DELAY V0, LD V1 0x01
+    let mut system = boot(Platform::Chip8E, &[0x60, 0x02, 0xf0, 0x4f, 0x61, 0x01]);
+    system.run_frame();
+
+    assert!(system.is_waiting());
+    assert_eq!(system.read_register(1), 0);
+
+    for _ in 0..3 {
+        system.run_frame();
+    }
+
+    assert!(!system.is_waiting());
+    assert_eq!(system.read_register(1), 1);
+}
+
+#[test]
+fn chip8x_loads_at_0x300_with_colour() {
+    let system = boot(Platform::Chip8X, &[0x13, 0x00]);
+
+    assert_eq!(system.pc(), 0x300);
+    assert!(system.colour_map().is_some());
+    assert!(boot(Platform::Chip8, &[0x12, 0x00]).colour_map().is_none());
+}
+
+#[test]
+fn chip8x_background_steps() {
+    let mut system = boot(Platform::Chip8X, &[0x02, 0xa0]);
+    assert_eq!(system.colour_map().unwrap().background(), COLOURS[2]);
+
+    steps(&mut system, 1);
+    assert_eq!(system.colour_map().unwrap().background(), COLOURS[0]);
+}
+
+#[rustfmt::skip]
+static CHIP8X_ZONE_ROM: [u8; 8] = [
+    0x60, 0x01, // LD V0 0x01, second 8 pixel column
+    0x61, 0x00, // LD V1 0x00, first block of 4 rows
+    0x62, 0x05, // LD V2 0x05, yellow
+    0xb0, 0x20, // COL V0 V2 0x0
+];
+
+#[test]
+fn chip8x_colour_zones() {
+    let mut system = boot(Platform::Chip8X, &CHIP8X_ZONE_ROM);
+    steps(&mut system, 4);
+
+    let map = system.colour_map().unwrap();
+    assert_eq!(map.foreground(8, 0), COLOURS[5]);
+    assert_eq!(map.foreground(15, 3), COLOURS[5]);
+    assert_eq!(map.foreground(8, 4), COLOURS[1]);
+    assert_eq!(map.foreground(0, 0), COLOURS[1]);
+}
+
+#[test]
+fn chip8x_nibble_add() {
+    // LD V0 0x36, LD V1 0x27, ADDN V0 V1
+    let mut system = boot(Platform::Chip8X, &[0x60, 0x36, 0x61, 0x27, 0x50, 0x11]);
+    steps(&mut system, 3);
+
+    assert_eq!(system.read_register(0), 0x55);
+}
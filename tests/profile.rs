@@ -0,0 +1,134 @@
+// The MIT License (MIT)
+
+// Copyright (c) 2021 AnonymousDapper
+
+use rusty_8::symbols::Symbols;
+use rusty_8::System;
+
+#[rustfmt::skip]
+static ROM: [u8; 18] = [
+    0x22, 0x06, // 0x200 CALL 0x206
+    0x22, 0x0c, // 0x202 CALL 0x20c
+    0x12, 0x04, // 0x204 JP 0x204
+    0x60, 0x05, // 0x206 LD V0 0x05
+    0x22, 0x0c, // 0x208 CALL 0x20c
+    0x00, 0xee, // 0x20a RET
+    0xa3, 0x00, // 0x20c LD I 0x300
+    0xf0, 0x33, // 0x20e BCD V0
+    0x00, 0xee, // 0x210 RET
+];
+
+fn profiled() -> System {
+    let mut system = System::new(rusty_8::DEFAULT_IPF);
+    system.enable_profiling();
+    system.load_rom(&ROM).unwrap();
+    system.run_frame();
+    system.run_frame();
+    system
+}
+
+#[test]
+fn counts_instructions_and_classes() {
+    let system = profiled();
+    let profiler = system.profiler().unwrap();
+
+    assert_eq!(profiler.count(0x200).instructions, 1);
+    assert_eq!(profiler.count(0x20c).instructions, 2);
+    assert_eq!(profiler.count(0x20e).cycles, 2 * 176);
+    assert_eq!(profiler.count(0x212).instructions, 0);
+
+    assert_eq!(profiler.class(0x2000).instructions, 3);
+    assert_eq!(profiler.class(0x0000).instructions, 3);
+}
+
+#[test]
+fn builds_the_call_tree() {
+    let system = profiled();
+    let profiler = system.profiler().unwrap();
+
+    let root = profiler.root();
+    let children: Vec<(u16, u64)> = profiler
+        .children(root)
+        .map(|child| (child.addr, child.calls))
+        .collect();
+    assert_eq!(children, [(0x206, 1), (0x20c, 1)]);
+
+    let draw = profiler.children(root).next().unwrap();
+    assert_eq!(draw.own.instructions, 3);
+    assert_eq!(profiler.total(draw).instructions, 6);
+    assert_eq!(profiler.total(root).instructions, system.instructions());
+
+    let (calls, own, total) = profiler.subroutines()[&0x20c];
+    assert_eq!(calls, 2);
+    assert_eq!(own.instructions, 6);
+    assert_eq!(total, own);
+}
+
+#[test]
+fn reports() {
+    let mut system = profiled();
+    let mut symbols = Symbols::new();
+    symbols.insert(0x206, "draw");
+    system.set_symbols(symbols);
+
+    let profiler = system.profiler().unwrap();
+    let report = profiler.report(&system, |addr| format!("op {:03x}", addr));
+
+    assert!(report.contains("0x020e draw+0x8  op 20e"));
+    assert!(report.contains("2nnn call"));
+    assert!(report.contains("100.0%  entry x1\n"));
+    assert!(report.contains("33.4%    draw x1\n"));
+    assert!(report.contains("21.1%      sub_20c x1\n"));
+
+    let folded = profiler.folded(&system);
+    let stacks: Vec<&str> = folded
+        .lines()
+        .map(|line| line.rsplit_once(' ').unwrap().0)
+        .collect();
+    assert_eq!(
+        stacks,
+        ["entry", "entry;draw", "entry;draw;sub_20c", "entry;sub_20c"]
+    );
+    assert!(folded.contains("entry;draw;sub_20c 278\n"));
+}
+
+#[rustfmt::skip]
+static RECURSIVE_ROM: [u8; 14] = [
+    0x60, 0x00, // 0x200 LD V0 0x00
+    0x22, 0x06, // 0x202 CALL 0x206
+    0x12, 0x04, // 0x204 JP 0x204
+    0x70, 0x01, // 0x206 ADD V0 0x01
+    0x30, 0x0c, // 0x208 SE V0 0x0c
+    0x22, 0x06, // 0x20a CALL 0x206
+    0x00, 0xee, // 0x20c RET
+];
+
+#[test]
+fn totals_of_recursive_calls() {
+    let mut system = System::new(rusty_8::DEFAULT_IPF);
+    system.enable_profiling();
+    system.load_rom(&RECURSIVE_ROM).unwrap();
+
+    for _ in 0..20 {
+        system.run_frame();
+    }
+
+    let profiler = system.profiler().unwrap();
+    assert_eq!(
+        profiler.total(profiler.root()).instructions,
+        system.instructions()
+    );
+
+    // 4 instructions a level, the last one skips its CALL. Each level's total counts the levels
+    // under it
+    let (calls, own, total) = profiler.subroutines()[&0x206];
+    assert_eq!(calls, 12);
+    assert_eq!(own.instructions, 12 * 4 - 1);
+    assert_eq!(
+        total.instructions,
+        (1..=12).map(|levels| levels * 4 - 1).sum::<u64>()
+    );
+
+    let report = profiler.report(&system, |_| String::new());
+    assert!(report.contains(&format!("{}sub_206 x1\n", "  ".repeat(12))));
+}
@@ -0,0 +1,73 @@
+// The MIT License (MIT)
+
+// Copyright (c) 2021 AnonymousDapper
+
+use rusty_8::System;
+
+#[rustfmt::skip]
+static DIRTY_ROM: [u8; 16] = [
+    0x60, 0x08, // LD V0 0x08
+    0x6a, 0x2a, // LD Va 0x2a
+    0xa3, 0x00, // LD I 0x300
+    0xfa, 0x55, // LD [I] Va
+    0xf0, 0x29, // LD F V0
+    0xd0, 0x15, // DRW V0 V1 0x5
+    0x22, 0x0e, // CALL 0x20e
+    0x12, 0x0e, // JP 0x20e
+];
+
+fn run(system: &mut System, frames: usize) {
+    for _ in 0..frames {
+        system.run_frame();
+    }
+}
+
+#[test]
+fn load_rom_starts_from_clean_machine() {
+    let mut system = System::new(rusty_8::DEFAULT_IPF);
+
+    system.load_rom(&DIRTY_ROM).unwrap();
+    run(&mut system, 4);
+
+    assert_eq!(system.read_register(0xa), 0x2a);
+    assert_eq!(system.memory().read_u8(0x30a), 0x2a);
+    assert!(system.display().iter().any(|&p| p != 0));
+    assert_eq!(system.memory().stack().len(), 1);
+
+    system.load_rom(&[0x12, 0x00]).unwrap();
+
+    assert_eq!(system.pc(), 0x200);
+    assert_eq!(system.ir(), 0);
+    assert!((0..16).all(|r| system.read_register(r) == 0));
+    assert!(system.display().iter().all(|&p| p == 0));
+    assert!(system.memory().stack().is_empty());
+    assert!(system.memory().ram()[0x202..].iter().all(|&b| b == 0));
+    assert!(!system.is_halted());
+
+    // The font survives the wipe
+    assert_eq!(
+        system.memory().ram()[0x50..0x55],
+        [0xf0, 0x90, 0x90, 0x90, 0xf0]
+    );
+}
+
+#[test]
+fn cls_clears_display() {
+    #[rustfmt::skip]
+    let rom = [
+        0xf0, 0x29, // LD F V0
+        0xd0, 0x15, // DRW V0 V1 0x5
+        0x00, 0xe0, // CLS
+        0x12, 0x06, // JP 0x206
+    ];
+
+    let mut system = System::new(rusty_8::DEFAULT_IPF);
+
+    system.load_rom(&rom).unwrap();
+    system.step();
+    system.step();
+    assert!(system.display().iter().any(|&p| p != 0));
+
+    system.step();
+    assert!(system.display().iter().all(|&p| p == 0));
+}
@@ -0,0 +1,70 @@
+// The MIT License (MIT)
+
+// Copyright (c) 2021 AnonymousDapper
+
+use rusty_8::platform::Platform;
+use rusty_8::rom::{Rom, RomError};
+use rusty_8::{GuestError, System};
+
+#[test]
+fn oversized_rom_is_rejected() {
+    let mut system = System::new(rusty_8::DEFAULT_IPF);
+    let rom = vec![0; 0x1000 - 0x200 + 1];
+
+    assert_eq!(
+        system.load_rom(&rom),
+        Err(RomError::TooLarge {
+            size: rom.len(),
+            max: 0x1000 - 0x200
+        })
+    );
+    assert_eq!(system.load_rom(&[]), Err(RomError::Empty));
+}
+
+#[test]
+fn known_rom_uses_database_entry() {
+    let rom = Rom::new(include_bytes!("../roms/pong.rom").to_vec());
+
+    assert!(rom.info.known);
+    assert_eq!(rom.info.title.as_deref(), Some("Pong (1 player)"));
+    assert_eq!(rom.info.tickrate, Some(7));
+    assert_eq!(rom.info.keymap.translate('w'), Some(0x1));
+}
+
+#[test]
+fn unknown_rom_guesses_platform() {
+    // CLS, HIGH, JP 0x202
+    let rom = Rom::new(vec![0x00, 0xe0, 0x00, 0xff, 0x12, 0x02]);
+
+    assert!(!rom.info.known);
+    assert_eq!(rom.info.platform, Platform::SuperChip);
+    assert_eq!(rom.info.quirks, Platform::SuperChip.quirks());
+}
+
+#[test]
+fn detected_schip_rom_is_unsupported() {
+    // CLS, HIGH, JP 0x202
+    let rom = Rom::new(vec![0x00, 0xe0, 0x00, 0xff, 0x12, 0x02]);
+    let mut system = System::new(rusty_8::DEFAULT_IPF);
+
+    assert_eq!(
+        rom.load_into(&mut system, None),
+        Err(RomError::UnsupportedPlatform(Platform::SuperChip))
+    );
+
+    // Asked for by name it runs up to the first SCHIP opcode, then halts
+    rom.load_into(&mut system, Some(Platform::SuperChip))
+        .unwrap();
+    for _ in 0..10 {
+        system.run_frame();
+    }
+
+    assert!(system.is_halted());
+    assert_eq!(
+        system.error(),
+        Some(GuestError::IllegalOpcode {
+            pc: 0x202,
+            op: 0x00ff
+        })
+    );
+}
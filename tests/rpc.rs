@@ -0,0 +1,203 @@
+// The MIT License (MIT)
+
+// Copyright (c) 2021 AnonymousDapper
+
+use rusty_8::rpc::Server;
+use rusty_8::System;
+use serde_json::{json, Value};
+
+#[rustfmt::skip]
+static ROM: [u8; 8] = [
+    0xf1, 0x0a, // 0x200 LD V1 K
+    0x22, 0x06, // 0x202 CALL 0x206
+    0x12, 0x04, // 0x204 JP 0x204
+    0x00, 0xe0, // 0x206 CLS, never returns
+];
+
+fn setup() -> (Server, System) {
+    let mut system = System::new(rusty_8::DEFAULT_IPF);
+    system.load_rom(&ROM).unwrap();
+
+    (Server::new(), system)
+}
+
+fn call(server: &mut Server, system: &mut System, request: Value) -> Value {
+    server.handle(system, &request.to_string()).unwrap()
+}
+
+#[test]
+fn keys_and_state() {
+    let (mut server, mut system) = setup();
+
+    let response = call(
+        &mut server,
+        &mut system,
+        json!({ "id": 1, "method": "run_frames" }),
+    );
+    assert_eq!(response["id"], 1);
+    assert_eq!(response["result"]["waiting"], true);
+
+    call(
+        &mut server,
+        &mut system,
+        json!({ "id": 2, "method": "set_keys", "params": { "keys": [9] } }),
+    );
+    let response = call(
+        &mut server,
+        &mut system,
+        json!({ "id": 3, "method": "step", "params": { "count": 2 } }),
+    );
+    let state = &response["result"];
+
+    assert_eq!(state["v"][1], 9);
+    assert_eq!(state["pc"], 0x208);
+    assert_eq!(state["stack"], json!([0x204]));
+    assert_eq!(state["error"], Value::Null);
+}
+
+#[test]
+fn framebuffer() {
+    let (mut server, mut system) = setup();
+    let response = call(
+        &mut server,
+        &mut system,
+        json!({ "id": "fb", "method": "get_framebuffer" }),
+    );
+    let result = &response["result"];
+
+    assert_eq!(response["id"], "fb");
+    assert_eq!(result["width"], 64);
+    assert_eq!(result["height"], 32);
+    assert_eq!(result["pixels"].as_array().unwrap().len(), 64 * 32);
+}
+
+#[test]
+fn states_are_saved_by_name() {
+    let (mut server, mut system) = setup();
+
+    call(
+        &mut server,
+        &mut system,
+        json!({ "id": 1, "method": "save_state", "params": { "name": "start" } }),
+    );
+    call(
+        &mut server,
+        &mut system,
+        json!({ "id": 2, "method": "run_frames", "params": { "count": 3 } }),
+    );
+    assert_eq!(system.frames(), 3);
+
+    let response = call(
+        &mut server,
+        &mut system,
+        json!({ "id": 3, "method": "load_state", "params": { "name": "start" } }),
+    );
+    assert_eq!(response["result"]["frames"], 0);
+    assert_eq!(system.frames(), 0);
+
+    let response = call(
+        &mut server,
+        &mut system,
+        json!({ "id": 4, "method": "load_state" }),
+    );
+    assert_eq!(response["error"]["message"], "no state saved as `default`");
+}
+
+#[test]
+fn errors() {
+    let (mut server, mut system) = setup();
+
+    let code = |server: &mut Server, system: &mut System, line: &str| {
+        server.handle(system, line).unwrap()["error"]["code"].clone()
+    };
+
+    assert_eq!(code(&mut server, &mut system, "{"), -32700);
+    assert_eq!(code(&mut server, &mut system, r#"{"id": 1}"#), -32600);
+    assert_eq!(
+        code(&mut server, &mut system, r#"{"id": 1, "method": "fly"}"#),
+        -32601
+    );
+    assert_eq!(
+        code(
+            &mut server,
+            &mut system,
+            r#"{"id": 1, "method": "set_keys", "params": {"keys": [16]}}"#
+        ),
+        -32602
+    );
+    assert_eq!(
+        code(
+            &mut server,
+            &mut system,
+            r#"{"id": 1, "method": "load", "params": {"path": "/nonexistent"}}"#
+        ),
+        -32000
+    );
+
+    // Notifications run but aren't answered
+    assert_eq!(server.handle(&mut system, r#"{"method": "step"}"#), None);
+    assert_eq!(system.instructions(), 1);
+}
+
+#[test]
+fn illegal_opcodes_are_errors() {
+    let mut system = System::new(rusty_8::DEFAULT_IPF);
+    system.load_rom(&[0x60, 0x01, 0xe0, 0xff]).unwrap(); // LD V0 1, then nothing known
+    let mut server = Server::new();
+
+    let response = call(
+        &mut server,
+        &mut system,
+        json!({ "id": 1, "method": "run_frames", "params": { "count": 10 } }),
+    );
+    assert_eq!(response["error"]["code"], -32001);
+    assert_eq!(
+        response["error"]["message"],
+        "illegal opcode 0xe0ff at 0x0202 for this platform"
+    );
+
+    // The server carries on and the machine stays where it stopped
+    let response = call(
+        &mut server,
+        &mut system,
+        json!({ "id": 2, "method": "get_state" }),
+    );
+    assert_eq!(response["result"]["halted"], true);
+    assert_eq!(response["result"]["v"][0], 1);
+    assert_eq!(system.frames(), 1);
+}
+
+#[test]
+fn counts_are_capped() {
+    let (mut server, mut system) = setup();
+
+    for method in ["step", "run_frames"] {
+        let response = call(
+            &mut server,
+            &mut system,
+            json!({ "id": 1, "method": method, "params": { "count": 1_000_000_000_000u64 } }),
+        );
+        assert_eq!(response["error"]["code"], -32602);
+    }
+
+    assert_eq!(system.instructions(), 0);
+}
+
+#[test]
+fn serves_a_line_per_request() {
+    let (mut server, mut system) = setup();
+    let input = b"{\"id\": 1, \"method\": \"get_state\"}\n\n{\"id\": 2, \"method\": \"step\"}\n";
+    let mut output = Vec::new();
+
+    server.serve(&mut system, &input[..], &mut output).unwrap();
+
+    let lines: Vec<Value> = String::from_utf8(output)
+        .unwrap()
+        .lines()
+        .map(|line| serde_json::from_str(line).unwrap())
+        .collect();
+
+    assert_eq!(lines.len(), 2);
+    assert_eq!(lines[0]["result"]["instructions"], 0);
+    assert_eq!(lines[1]["result"]["instructions"], 1);
+}
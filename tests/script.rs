@@ -0,0 +1,133 @@
+// The MIT License (MIT)
+
+// Copyright (c) 2021 AnonymousDapper
+
+use std::path::Path;
+
+use rusty_8::script::Script;
+use rusty_8::System;
+
+#[rustfmt::skip]
+static ROM: [u8; 12] = [
+    0xf1, 0x0a, // 0x200 LD V1 K
+    0xa3, 0x00, // 0x202 LD I 0x300
+    0xf1, 0x55, // 0x204 LD [I] V1
+    0xf1, 0x29, // 0x206 LD F V1
+    0xd2, 0x25, // 0x208 DRW V2 V2 0x5
+    0x12, 0x0a, // 0x20a JP 0x20a
+];
+
+fn run(text: &str) -> (System, Result<(), String>, String) {
+    let mut system = System::new(rusty_8::DEFAULT_IPF);
+    system.load_rom(&ROM).unwrap();
+
+    let mut out = Vec::new();
+    let result = Script::parse(text)
+        .and_then(|script| script.run(&mut system, Path::new(""), &mut out))
+        .map_err(|e| e.to_string());
+
+    (system, result, String::from_utf8(out).unwrap())
+}
+
+#[test]
+fn keys_waits_and_assertions() {
+    let (system, result, out) = run("
+        frames 2
+        assert pc == 202  # waiting on a key
+        tap 7
+        wait mem 0x301 == 7 within 10
+        print v1
+        assert pixels > 0
+        assert pixel 0 0 == 1
+        assert i == 0x300  # the digit's glyph
+    ");
+
+    assert_eq!(
+        result,
+        Err(String::from("line 9: assertion failed, `i` is 115 (0x73)"))
+    );
+    assert_eq!(out, "v1 = 7 (0x7)\n");
+    assert_eq!(system.read_register(1), 7);
+}
+
+#[test]
+fn waits_time_out() {
+    let (system, result, _) = run("wait v1 == 7 within 5");
+
+    assert_eq!(
+        result,
+        Err(String::from("line 1: `v1` still 0 after 5 frames"))
+    );
+    assert_eq!(system.frames(), 5);
+}
+
+#[test]
+fn addresses_compare_as_hex() {
+    let (_, result, _) = run("
+        frames 1
+        assert pc != 0x202
+    ");
+    assert_eq!(
+        result,
+        Err(String::from(
+            "line 3: assertion failed, `pc` is 514 (0x202)"
+        ))
+    );
+}
+
+#[test]
+fn parse_errors_name_the_line() {
+    let errors = [
+        (
+            "frames",
+            "line 1: unknown command or wrong arguments for `frames`",
+        ),
+        ("\n\ntap g", "line 3: `g` is not a key, expected 0-f"),
+        ("assert vg == 1", "line 1: `vg` is not a register"),
+        ("assert v1 = 1", "line 1: `=` is not a comparison"),
+        (
+            "wait pc == 0x200 after 3",
+            "line 1: expected `within FRAMES` after the condition",
+        ),
+        ("platform gameboy", "line 1: `gameboy` is not a platform"),
+    ];
+
+    for (text, message) in errors.iter() {
+        assert_eq!(Script::parse(text).unwrap_err().to_string(), *message);
+    }
+
+    assert_eq!(Script::parse("# only a comment\n\n").unwrap().len(), 0);
+}
+
+#[test]
+fn loads_roms_and_takes_screenshots() {
+    let dir = std::env::temp_dir().join(format!("rusty-8-script-{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    std::fs::write(dir.join("key.ch8"), ROM).unwrap();
+    std::fs::write(dir.join("key.sym"), "0x20a done\n").unwrap();
+
+    let script = Script::parse(
+        "
+        load key.ch8
+        symbols key.sym
+        frames 1
+        press 3
+        wait pc == done
+        screenshot shot.ppm
+        ",
+    )
+    .unwrap();
+
+    let mut system = System::new(rusty_8::DEFAULT_IPF);
+    let result = script.run(&mut system, &dir, &mut Vec::new());
+    let image = std::fs::read(dir.join("shot.ppm"));
+    std::fs::remove_dir_all(&dir).unwrap();
+
+    assert_eq!(result, Ok(()));
+    assert_eq!(system.read_register(1), 3);
+
+    let image = image.unwrap();
+    let header = b"P6\n64 32\n255\n";
+    assert!(image.starts_with(header));
+    assert_eq!(image.len(), header.len() + 64 * 32 * 3);
+}
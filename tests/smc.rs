@@ -0,0 +1,104 @@
+// The MIT License (MIT)
+
+// Copyright (c) 2021 AnonymousDapper
+
+use rusty_8::smc::Patch;
+use rusty_8::System;
+
+#[rustfmt::skip]
+static ROM: [u8; 18] = [
+    0x60, 0x12, // 0x200 LD V0 0x12
+    0x61, 0x0c, // 0x202 LD V1 0x0c
+    0xa2, 0x0a, // 0x204 LD I 0x20a
+    0xf1, 0x55, // 0x206 LD [I] V1, patches 0x20a into JP 0x20c
+    0x60, 0x60, // 0x208 LD V0 0x60
+    0x00, 0x00, // 0x20a
+    0xa2, 0x00, // 0x20c LD I 0x200
+    0xf0, 0x55, // 0x20e LD [I] V0, rewrites 0x200 with what it already holds
+    0x12, 0x00, // 0x210 JP 0x200
+];
+
+fn patches(frames: usize) -> (System, Vec<Patch>) {
+    let mut system = System::new(rusty_8::DEFAULT_IPF);
+    system.enable_smc_detection();
+    system.load_rom(&ROM).unwrap();
+
+    for _ in 0..frames {
+        system.run_frame();
+    }
+
+    let patches = system.smc().unwrap().patches().copied().collect();
+    (system, patches)
+}
+
+#[test]
+fn writes_before_code_runs() {
+    let (system, patches) = patches(1);
+    let smc = system.smc().unwrap();
+
+    let before: Vec<(u16, u16, u16)> = patches
+        .iter()
+        .filter(|patch| !patch.ran_before)
+        .map(|patch| (patch.writer, patch.addr, patch.instruction))
+        .collect();
+
+    assert_eq!(before, [(0x206, 0x20a, 0x20a), (0x206, 0x20b, 0x20a)]);
+    assert!(smc.is_patched(0x20a));
+    assert!(!smc.is_patched(0x206));
+}
+
+#[test]
+fn writes_after_code_ran() {
+    let (_, patches) = patches(2);
+
+    let after: Vec<(u16, u16, u16, u64)> = patches
+        .iter()
+        .filter(|patch| patch.ran_before)
+        .map(|patch| (patch.writer, patch.addr, patch.instruction, patch.count))
+        .collect();
+
+    // The second time round, 0x20a has already run
+    assert_eq!(
+        after,
+        [
+            (0x206, 0x20a, 0x20a, 1),
+            (0x206, 0x20b, 0x20a, 1),
+            (0x20e, 0x200, 0x200, 2)
+        ]
+    );
+}
+
+#[test]
+fn data_writes_are_ignored() {
+    let mut system = System::new(rusty_8::DEFAULT_IPF);
+    system.enable_smc_detection();
+
+    // LD I 0x300, LD [I] V3, JP 0x204
+    system
+        .load_rom(&[0xa3, 0x00, 0xf3, 0x55, 0x12, 0x04])
+        .unwrap();
+    system.run_frame();
+
+    let smc = system.smc().unwrap();
+    assert!(smc.is_empty());
+    assert_eq!(
+        smc.report(&system, |_| String::new()),
+        "no self-modifying code found\n"
+    );
+}
+
+#[test]
+fn report_decodes_patched_code() {
+    let (system, _) = patches(1);
+    let report = system
+        .smc()
+        .unwrap()
+        .report(&system, |addr| format!("op {:03x}", addr));
+
+    assert!(report.contains(
+        "0x0206 wrote 0x020a before it ran, patched 1x | 0x020a (0x0000 -> 0x120c) op 20a"
+    ));
+    assert!(report.contains(
+        "0x020e wrote 0x0200 after it ran, patched 1x | 0x0200 (0x6012 -> 0x6012) op 200"
+    ));
+}
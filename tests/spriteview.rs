@@ -0,0 +1,49 @@
+// The MIT License (MIT)
+
+// Copyright (c) 2021 AnonymousDapper
+
+use rusty_8::platform::Platform;
+use rusty_8::spriteview::{pending_sprite, Sprite};
+use rusty_8::System;
+
+#[rustfmt::skip]
+static ROM: [u8; 6] = [
+    0xa3, 0x00, // LD I 0x300
+    0xd0, 0x15, // DRW V0 V1 0x5
+    0xd0, 0x10, // DRW V0 V1 0x0
+];
+
+#[test]
+fn pending_sprite_follows_drw() {
+    let mut system = System::new(rusty_8::DEFAULT_IPF);
+    system.load_rom(&ROM).unwrap();
+
+    assert_eq!(pending_sprite(&system), None);
+    system.step();
+    assert_eq!(
+        pending_sprite(&system),
+        Some(Sprite {
+            addr: 0x300,
+            rows: 5,
+            wide: false
+        })
+    );
+}
+
+#[test]
+fn schip_draws_16x16_sprites() {
+    let mut system = System::new(rusty_8::DEFAULT_IPF);
+    system.set_platform(Platform::SuperChip);
+    system.load_rom(&ROM).unwrap();
+    system.step();
+    system.step();
+
+    assert_eq!(
+        pending_sprite(&system),
+        Some(Sprite {
+            addr: 0x300,
+            rows: 16,
+            wide: true
+        })
+    );
+}
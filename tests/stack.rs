@@ -0,0 +1,94 @@
+// The MIT License (MIT)
+
+// Copyright (c) 2021 AnonymousDapper
+
+use rusty_8::platform::Platform;
+use rusty_8::{GuestError, System};
+
+fn run(system: &mut System, frames: usize) {
+    for _ in 0..frames {
+        system.run_frame();
+    }
+}
+
+#[test]
+fn runaway_recursion_overflows() {
+    let rom = [0x22, 0x00]; // CALL 0x200
+
+    for (platform, depth) in [(Platform::Chip8, 12), (Platform::SuperChip, 16)] {
+        let mut system = System::new(rusty_8::DEFAULT_IPF);
+        system.set_platform(platform);
+        system.load_rom(&rom).unwrap();
+        run(&mut system, 10);
+
+        assert!(system.is_halted());
+        assert_eq!(system.memory().stack().len(), depth);
+        assert_eq!(
+            system.error(),
+            Some(GuestError::StackOverflow { pc: 0x200, depth })
+        );
+    }
+}
+
+#[test]
+fn stack_depth_is_configurable() {
+    let mut system = System::new(rusty_8::DEFAULT_IPF);
+    system.set_stack_depth(3);
+    system.load_rom(&[0x22, 0x00]).unwrap();
+    run(&mut system, 1);
+
+    assert_eq!(
+        system.error(),
+        Some(GuestError::StackOverflow {
+            pc: 0x200,
+            depth: 3
+        })
+    );
+}
+
+#[test]
+fn ret_outside_call_underflows() {
+    let mut system = System::new(rusty_8::DEFAULT_IPF);
+    system.load_rom(&[0x00, 0xee]).unwrap();
+    run(&mut system, 1);
+
+    assert!(system.is_halted());
+    assert_eq!(
+        system.error(),
+        Some(GuestError::StackUnderflow { pc: 0x200 })
+    );
+
+    // A restart clears the fault
+    system.restart();
+    assert_eq!(system.error(), None);
+    assert!(!system.is_halted());
+}
+
+#[test]
+fn vip_stack_lives_in_ram() {
+    #[rustfmt::skip]
+    let rom = [
+        0x22, 0x08, // CALL 0x208
+        0x12, 0x02, // JP 0x202
+        0x12, 0x04, // JP 0x204
+        0x12, 0x06, // JP 0x206
+        0x60, 0x02, // LD V0 0x02
+        0x61, 0x06, // LD V1 0x06
+        0xae, 0xce, // LD I 0xece
+        0xf1, 0x55, // LD [I] V1
+        0x00, 0xee, // RET
+    ];
+
+    let mut system = System::new(rusty_8::DEFAULT_IPF);
+    system.load_rom(&rom).unwrap();
+    system.step();
+
+    assert_eq!(system.memory().read_u16(0xece), 0x202);
+
+    for _ in 0..5 {
+        system.step();
+    }
+
+    // The return address was overwritten in RAM
+    assert_eq!(system.pc(), 0x206);
+}
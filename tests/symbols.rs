@@ -0,0 +1,56 @@
+// The MIT License (MIT)
+
+// Copyright (c) 2021 AnonymousDapper
+
+use rusty_8::symbols::{SymbolError, Symbols};
+
+static SYMBOLS: &str = "
+# Octo style
+main = 0x200
+draw = 0x2a4
+
+0x300 sprites ; plain list
+0x310 beef
+";
+
+#[test]
+fn parses_both_formats() {
+    let symbols = Symbols::parse(SYMBOLS).unwrap();
+
+    assert_eq!(symbols.len(), 4);
+    assert_eq!(symbols.name(0x200), Some("main"));
+    assert_eq!(symbols.addr("draw"), Some(0x2a4));
+    assert_eq!(symbols.name(0x300), Some("sprites"));
+    assert_eq!(symbols.addr("beef"), Some(0x310));
+}
+
+#[test]
+fn bad_lines_are_reported() {
+    assert_eq!(
+        Symbols::parse("main = 0x200\nmain\n"),
+        Err(SymbolError {
+            line: 2,
+            text: String::from("main")
+        })
+    );
+}
+
+#[test]
+fn names_win_over_hex() {
+    let symbols = Symbols::parse(SYMBOLS).unwrap();
+
+    assert_eq!(symbols.resolve("beef"), Some(0x310));
+    assert_eq!(symbols.resolve("0x2a6"), Some(0x2a6));
+    assert_eq!(symbols.resolve("cafe"), Some(0xcafe));
+    assert_eq!(symbols.resolve("nowhere"), None);
+}
+
+#[test]
+fn addresses_are_described_from_the_nearest_symbol() {
+    let symbols = Symbols::parse(SYMBOLS).unwrap();
+
+    assert_eq!(symbols.describe(0x2a4).as_deref(), Some("draw"));
+    assert_eq!(symbols.describe(0x2b0).as_deref(), Some("draw+0xc"));
+    assert_eq!(symbols.describe(0x100), None);
+    assert_eq!(symbols.annotate("fault", 0x202), "fault in main+0x2");
+}
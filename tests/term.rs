@@ -0,0 +1,145 @@
+// The MIT License (MIT)
+
+// Copyright (c) 2021 AnonymousDapper
+
+use std::io::{self, Read, Write};
+use std::sync::atomic::Ordering;
+use std::sync::{Arc, Mutex};
+
+use rusty_8::dis::Disassembler;
+use rusty_8::display::{Renderer, Screen};
+use rusty_8::keymap::Keymap;
+use rusty_8::{term, System};
+
+static IBM_LOGO: &[u8] = include_bytes!("../roms/ibm_logo.ch8");
+
+// Output shared with the test after the screen has been moved into the run loop
+#[derive(Clone, Default)]
+struct Shared(Arc<Mutex<Vec<u8>>>);
+
+impl Write for Shared {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.0.lock().unwrap().extend_from_slice(buf);
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+// No keys for as many reads as `frames`, the run loop reads once a frame, then CTRL+C
+struct QuitAfter(usize);
+
+impl Read for QuitAfter {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        if self.0 == 0 {
+            buf[0] = 0x03;
+            Ok(1)
+        } else {
+            self.0 -= 1;
+            Ok(0)
+        }
+    }
+}
+
+fn screen(out: &Shared, debug: bool) -> Screen {
+    let mut screen = Screen::new("test", Renderer::Ascii, debug);
+    screen.set_term_size((200, 60));
+    screen.set_output(Box::new(out.clone()));
+    screen
+}
+
+fn run(frames: usize, dis: Option<Disassembler>) -> (System, String) {
+    let mut system = System::new(rusty_8::DEFAULT_IPF);
+    system.load_rom(IBM_LOGO).unwrap();
+
+    let out = Shared::default();
+    let screen = screen(&out, dis.is_some());
+
+    term::run_with_input(
+        &mut system,
+        screen,
+        dis,
+        &Keymap::default(),
+        None,
+        QuitAfter(frames),
+    );
+
+    let text = String::from_utf8_lossy(&out.0.lock().unwrap()).into_owned();
+    (system, text)
+}
+
+fn assert_send<T: Send>() {}
+
+#[test]
+fn machines_and_screens_can_move_between_threads() {
+    assert_send::<System>();
+    assert_send::<Screen>();
+}
+
+#[test]
+fn draws_to_the_given_output() {
+    let (system, out) = run(0, None);
+
+    assert!(system.is_halted());
+    assert!(out.contains("CHIP-8 Emulator (test)"));
+}
+
+#[test]
+fn debugger_panes_draw_to_the_given_output() {
+    let (_, out) = run(2, Some(Disassembler::new()));
+
+    // Laid out for the size given, whatever terminal runs the tests
+    assert!(out.contains("< Registers >"));
+    assert!(out.contains("PC : \x1b[93;40m0x02"));
+}
+
+#[test]
+fn interrupt_flag_belongs_to_its_screen() {
+    let (out, other) = (Shared::default(), Shared::default());
+    let (screen, other) = (screen(&out, false), screen(&other, false));
+
+    screen.interrupt_flag().store(true, Ordering::Relaxed);
+    assert!(screen.take_interrupt());
+    assert!(!other.take_interrupt());
+
+    let mut system = System::new(rusty_8::DEFAULT_IPF);
+    system.load_rom(IBM_LOGO).unwrap();
+    screen.interrupt_flag().store(true, Ordering::Relaxed);
+
+    // Stops before the first frame without any input
+    term::run_with_input(
+        &mut system,
+        screen,
+        None,
+        &Keymap::default(),
+        None,
+        QuitAfter(usize::MAX),
+    );
+    assert_eq!(system.instructions(), 0);
+}
+
+#[test]
+fn screens_run_side_by_side() {
+    const FRAMES: usize = 12;
+
+    let (expected, expected_out) = run(FRAMES, None);
+    assert!(expected.instructions() > 0);
+    assert!(expected.display().contains(&1));
+
+    let threads: Vec<_> = (0..4)
+        .map(|_| std::thread::spawn(|| run(FRAMES, None)))
+        .collect();
+
+    for thread in threads {
+        let (system, out) = thread.join().unwrap();
+
+        assert_eq!(system.pc(), expected.pc());
+        assert_eq!(system.ir(), expected.ir());
+        assert_eq!(system.instructions(), expected.instructions());
+        assert_eq!(system.frames(), expected.frames());
+        assert_eq!(system.display(), expected.display());
+        assert_eq!(out, expected_out);
+    }
+}
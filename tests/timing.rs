@@ -0,0 +1,117 @@
+// The MIT License (MIT)
+
+// Copyright (c) 2021 AnonymousDapper
+
+use rusty_8::timing::{self, Timing};
+use rusty_8::System;
+
+#[rustfmt::skip]
+static DT_ROM: [u8; 8] = [
+    0x60, 0x3c, // LD V0 0x3c
+    0xf0, 0x15, // LD DT V0
+    0xf1, 0x07, // LD V1 DT
+    0x12, 0x04, // JP 0x204
+];
+
+fn delay_after(ipf: usize, frames: usize) -> u8 {
+    let mut system = System::new(ipf);
+    system.load_rom(&DT_ROM).unwrap();
+
+    for _ in 0..frames {
+        system.run_frame();
+    }
+
+    system.read_register(1)
+}
+
+#[test]
+fn timers_ignore_instruction_rate() {
+    assert_eq!(delay_after(7, 30), 31);
+    assert_eq!(delay_after(1000, 30), 31);
+    assert_eq!(delay_after(1000, 60), 1);
+    assert_eq!(delay_after(1000, 61), 0);
+}
+
+#[test]
+fn ipf_is_clamped() {
+    let mut system = System::new(0);
+    assert_eq!(system.ipf(), 1);
+
+    system.set_ipf(usize::MAX);
+    assert_eq!(system.ipf(), rusty_8::MAX_IPF);
+}
+
+#[test]
+fn vip_draw_waits_for_vblank() {
+    #[rustfmt::skip]
+    let rom = [
+        0x71, 0x01, // ADD V1 0x01
+        0xd0, 0x01, // DRW V0 V0 0x1
+        0x12, 0x00, // JP 0x200
+    ];
+
+    let mut system = System::new(rusty_8::DEFAULT_IPF);
+    system.set_timing(Timing::Vip);
+    system.load_rom(&rom).unwrap();
+
+    for _ in 0..20 {
+        system.run_frame();
+    }
+
+    // One pass through the loop per frame, however many cycles are left over
+    assert_eq!(system.read_register(1), 20);
+}
+
+#[test]
+fn vip_draw_runs_after_vblank() {
+    #[rustfmt::skip]
+    let rom = [
+        0xa2, 0x06, // LD I 0x206
+        0xd0, 0x01, // DRW V0 V0 0x1
+        0x12, 0x04, // JP 0x204
+        0x80, 0x00, // Sprite with only its top left pixel set
+    ];
+
+    let mut system = System::new(rusty_8::DEFAULT_IPF);
+    system.set_timing(Timing::Vip);
+    system.load_rom(&rom).unwrap();
+
+    system.run_frame();
+    assert_eq!(system.pc(), 0x202);
+    assert_eq!(system.display()[0], 0);
+
+    system.run_frame();
+    assert_eq!(system.display()[0], 1);
+}
+
+#[test]
+fn vip_rate_ignores_ipf() {
+    #[rustfmt::skip]
+    let rom = [
+        0x71, 0x01, // ADD V1 0x01
+        0x12, 0x00, // JP 0x200
+    ];
+
+    let instructions = |ipf| {
+        let mut system = System::new(ipf);
+        system.set_timing(Timing::Vip);
+        system.load_rom(&rom).unwrap();
+        system.run_frame();
+
+        system.instructions()
+    };
+
+    // Instructions keep running until the frame's cycles are used up
+    let costs = [
+        timing::vip_cycles(0x7101, 0, false),
+        timing::vip_cycles(0x1200, 0, false),
+    ];
+    let (mut spent, mut expected) = (0, 0);
+    while spent < timing::VIP_FRAME_CYCLES {
+        spent += costs[expected as usize % 2];
+        expected += 1;
+    }
+
+    assert_eq!(instructions(1), expected);
+    assert_eq!(instructions(1000), expected);
+}
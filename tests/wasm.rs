@@ -0,0 +1,51 @@
+// The MIT License (MIT)
+
+// Copyright (c) 2021 AnonymousDapper
+
+// Runs natively with `--features wasm`, and headless under node with
+// `cargo test --target wasm32-unknown-unknown --no-default-features --features wasm --test wasm`
+
+#[cfg(target_arch = "wasm32")]
+use wasm_bindgen_test::wasm_bindgen_test as test;
+
+use rusty_8::wasm::Emulator;
+
+#[rustfmt::skip]
+static ROM: [u8; 12] = [
+    0xf1, 0x0a, // 0x200 LD V1 K
+    0xa2, 0x0a, // 0x202 LD I 0x20a
+    0xd0, 0x01, // 0x204 DRW V0 V0 0x1
+    0x12, 0x06, // 0x206 JP 0x206
+    0x00, 0x00,
+    0x80, 0x00, // 0x20a Sprite with only its top left pixel set
+];
+
+#[test]
+fn runs_frames_with_keys() {
+    let mut emulator = Emulator::new();
+    emulator.load_rom(&ROM).unwrap();
+    assert!(emulator.take_redraw());
+
+    // Nothing is drawn until a key goes down
+    emulator.run_frame();
+    assert!(!emulator.take_redraw());
+    assert!(emulator.framebuffer().iter().all(|&pixel| pixel == 0));
+
+    emulator.set_key(4, true);
+    emulator.run_frame();
+
+    let framebuffer = emulator.framebuffer();
+    assert!(emulator.take_redraw());
+    assert_eq!(framebuffer.len(), emulator.width() * emulator.height());
+    assert_eq!(framebuffer[0], 1);
+    assert_eq!(framebuffer.iter().filter(|&&pixel| pixel != 0).count(), 1);
+    assert!(!emulator.halted());
+}
+
+#[test]
+fn bad_roms_are_errors() {
+    let mut emulator = Emulator::new();
+
+    assert_eq!(emulator.load_rom(&[]), Err(String::from("ROM is empty")));
+    assert!(emulator.load_rom(&[0; 0x1000]).is_err());
+}
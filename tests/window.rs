@@ -0,0 +1,63 @@
+// The MIT License (MIT)
+
+// Copyright (c) 2021 AnonymousDapper
+
+use minifb::Key;
+
+use rusty_8::palette::Palette;
+use rusty_8::window;
+use rusty_8::{System, DISPLAY_HEIGHT, DISPLAY_WIDTH};
+
+#[rustfmt::skip]
+static GLYPH_ROM: [u8; 8] = [
+    0x60, 0x00, // LD V0 0x00
+    0xf0, 0x29, // LD F V0
+    0xd0, 0x05, // DRW V0 V0 5
+    0x12, 0x06, // JP 0x206
+];
+
+#[test]
+fn keys_map_to_host_keys() {
+    assert_eq!(window::host_key('0'), Some(Key::Key0));
+    assert_eq!(window::host_key('9'), Some(Key::Key9));
+    assert_eq!(window::host_key('a'), Some(Key::A));
+    assert_eq!(window::host_key('Z'), Some(Key::Z));
+    assert_eq!(window::host_key(';'), None);
+}
+
+#[test]
+fn palette_colours_are_packed_rgb() {
+    let palette = Palette::from_hex_list("000000,ff8000,00ff00,123456").unwrap();
+
+    assert_eq!(
+        window::pixel_colours(&palette),
+        [0x000000, 0xff8000, 0x00ff00, 0x123456]
+    );
+}
+
+#[test]
+fn pixels_are_scaled_into_squares() {
+    const SCALE: usize = 3;
+
+    let mut system = System::new(rusty_8::DEFAULT_IPF);
+    system.load_rom(&GLYPH_ROM).unwrap();
+    system.run_frame();
+
+    let colours = [0x000000, 0xffffff, 0, 0];
+    let width = DISPLAY_WIDTH * SCALE;
+    let mut buffer = vec![0x123456; width * DISPLAY_HEIGHT * SCALE];
+    window::draw(&system, &colours, SCALE, &mut buffer);
+
+    let at = |x: usize, y: usize| buffer[x + y * width];
+
+    // Top left corner of the 0 glyph, 0xf0 then 0x90
+    for (x, y) in (0..SCALE).flat_map(|x| (0..SCALE).map(move |y| (x, y))) {
+        assert_eq!(at(x, y), 0xffffff);
+        assert_eq!(at(x, y + SCALE), 0xffffff);
+    }
+
+    assert_eq!(at(3 * SCALE, 0), 0xffffff);
+    assert_eq!(at(4 * SCALE, 0), 0x000000);
+    assert_eq!(at(SCALE, SCALE), 0x000000);
+    assert_eq!(at(width - 1, DISPLAY_HEIGHT * SCALE - 1), 0x000000);
+}